@@ -1,17 +1,11 @@
-use dotenv::dotenv;
-use ftx::rest::{Rest, Result};
-use std::env::var;
+use ftx::credentials::Credentials;
+use ftx::rest::Result;
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    dotenv().ok();
-
-    let api = Rest::new(
-        var("API_KEY").expect("API Key is not defined."),
-        var("API_SECRET").expect("API Secret is not defined."),
-        var("SUBACCOUNT").ok(),
-        None,
-    );
+    let api = Credentials::from_env()
+        .expect("API credentials are not defined.")
+        .rest()?;
 
     let price = api.get_market("BTC/USD").await?.price;
     println!("1 BTC is worth {} USD.", price);
@@ -1,16 +1,11 @@
-use dotenv::dotenv;
-use ftx::rest::Rest;
-use std::env::var;
+use ftx::credentials::Credentials;
 
 #[tokio::main]
 async fn main() {
-    dotenv().ok();
-    let api = Rest::new(
-        var("API_KEY").expect("API key not defined"),
-        var("API_SECRET").expect("API secret not defined"),
-        var("SUBACCOUNT").ok(),
-        None,
-    );
+    let api = Credentials::from_env()
+        .expect("API credentials not defined")
+        .rest()
+        .expect("Client could not be built");
     println!("Account:");
     println!("{:#?}", api.get_account().await.unwrap());
     println!("Positions:");
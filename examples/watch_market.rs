@@ -1,24 +1,13 @@
-use dotenv::dotenv;
+use ftx::credentials::Credentials;
 use ftx::ws::Result;
-use ftx::ws::{Channel, Data, Orderbook, Ws};
+use ftx::ws::{Channel, Data, Orderbook};
 use futures::stream::StreamExt;
-use std::env::var;
 use std::io;
 use std::io::Write;
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    dotenv().ok();
-
-    let mut websocket = Ws::connect(
-        Some((
-            var("API_KEY").expect("API Key is not defined."),
-            var("API_SECRET").expect("API Secret is not defined."),
-        )),
-        var("SUBACCOUNT").ok(),
-        None,
-    )
-    .await?;
+    let mut websocket = Credentials::from_env()?.ws().await?;
 
     let market = String::from("BTC-PERP");
     let mut orderbook = Orderbook::new(market.to_owned());
@@ -0,0 +1,40 @@
+-- Every table keeps the full record as JSON in `data`, next to the columns
+-- used for syncing and filtering. Use json_extract for other fields.
+
+CREATE TABLE fills (
+    id INTEGER PRIMARY KEY,
+    market TEXT,
+    time TEXT NOT NULL,
+    data TEXT NOT NULL
+);
+
+CREATE INDEX fills_time ON fills (time);
+
+CREATE TABLE orders (
+    id INTEGER PRIMARY KEY,
+    market TEXT NOT NULL,
+    status TEXT NOT NULL,
+    created_at TEXT NOT NULL,
+    data TEXT NOT NULL
+);
+
+CREATE INDEX orders_created_at ON orders (created_at);
+
+CREATE TABLE deposits (
+    id INTEGER PRIMARY KEY,
+    coin TEXT NOT NULL,
+    status TEXT NOT NULL,
+    time TEXT NOT NULL,
+    data TEXT NOT NULL
+);
+
+CREATE INDEX deposits_time ON deposits (time);
+
+CREATE TABLE funding_payments (
+    id INTEGER PRIMARY KEY,
+    future TEXT NOT NULL,
+    time TEXT NOT NULL,
+    data TEXT NOT NULL
+);
+
+CREATE INDEX funding_payments_time ON funding_payments (time);
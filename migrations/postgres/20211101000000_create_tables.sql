@@ -0,0 +1,80 @@
+CREATE TYPE side AS ENUM ('buy', 'sell');
+CREATE TYPE order_type AS ENUM ('market', 'limit');
+CREATE TYPE order_status AS ENUM ('new', 'open', 'closed');
+CREATE TYPE conditional_order_type AS ENUM ('stop', 'trailing_stop', 'take_profit');
+CREATE TYPE conditional_order_status AS ENUM ('open', 'cancelled', 'triggered');
+CREATE TYPE fill_type AS ENUM ('otc', 'order');
+
+CREATE TABLE fills (
+    id BIGINT PRIMARY KEY,
+    market TEXT,
+    future TEXT,
+    type fill_type NOT NULL,
+    side side NOT NULL,
+    price NUMERIC NOT NULL,
+    size NUMERIC NOT NULL,
+    time TIMESTAMPTZ NOT NULL,
+    fee NUMERIC NOT NULL,
+    fee_currency TEXT NOT NULL,
+    fee_rate NUMERIC NOT NULL,
+    liquidity TEXT NOT NULL,
+    base_currency TEXT,
+    quote_currency TEXT,
+    order_id BIGINT,
+    trade_id BIGINT
+);
+
+CREATE INDEX fills_market_time ON fills (market, time);
+
+CREATE TABLE orders (
+    id BIGINT PRIMARY KEY,
+    market TEXT NOT NULL,
+    future TEXT,
+    type order_type NOT NULL,
+    side side NOT NULL,
+    price NUMERIC,
+    size NUMERIC NOT NULL,
+    reduce_only BOOLEAN NOT NULL,
+    ioc BOOLEAN NOT NULL,
+    post_only BOOLEAN NOT NULL,
+    status order_status NOT NULL,
+    filled_size NUMERIC NOT NULL,
+    remaining_size NUMERIC NOT NULL,
+    avg_fill_price NUMERIC,
+    liquidation BOOLEAN,
+    created_at TIMESTAMPTZ NOT NULL,
+    client_id TEXT
+);
+
+CREATE INDEX orders_market_created_at ON orders (market, created_at);
+
+CREATE TABLE conditional_orders (
+    id BIGINT PRIMARY KEY,
+    market TEXT NOT NULL,
+    future TEXT,
+    order_type order_type NOT NULL,
+    type conditional_order_type NOT NULL,
+    side side NOT NULL,
+    price NUMERIC,
+    size NUMERIC NOT NULL,
+    status conditional_order_status NOT NULL,
+    trail_start NUMERIC,
+    trail_value NUMERIC,
+    trigger_price NUMERIC NOT NULL,
+    triggered_at TIMESTAMPTZ,
+    filled_size NUMERIC,
+    avg_fill_price NUMERIC,
+    created_at TIMESTAMPTZ NOT NULL,
+    client_id TEXT,
+    order_price NUMERIC,
+    retry_until_filled BOOLEAN NOT NULL
+);
+
+CREATE TABLE funding_payments (
+    id BIGINT PRIMARY KEY,
+    future TEXT NOT NULL,
+    payment NUMERIC NOT NULL,
+    time TIMESTAMPTZ NOT NULL
+);
+
+CREATE INDEX funding_payments_future_time ON funding_payments (future, time);
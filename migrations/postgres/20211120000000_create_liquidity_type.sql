@@ -0,0 +1,3 @@
+CREATE TYPE liquidity AS ENUM ('maker', 'taker');
+
+ALTER TABLE fills ALTER COLUMN liquidity TYPE liquidity USING liquidity::liquidity;
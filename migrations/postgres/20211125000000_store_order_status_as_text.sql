@@ -0,0 +1,4 @@
+-- Statuses FTX adds later are stored as they are instead of being rejected
+ALTER TABLE orders ALTER COLUMN status TYPE TEXT USING status::TEXT;
+
+DROP TYPE order_status;
@@ -0,0 +1,326 @@
+//! This module keeps a local SQLite journal of fills, order history, deposits
+//! and funding payments. It is only available with the `archive` feature.
+//!
+//! `Archive::sync` fetches what is newer than the last stored record of each
+//! table, so a journal that is synced regularly only requests the latest
+//! pages. Orders and deposits that had not reached a final status are fetched
+//! again until they do. Every table keeps the full record as JSON in its
+//! `data` column, next to the columns used for syncing and filtering.
+
+mod error;
+
+pub use error::*;
+
+use crate::rest::{FillInfo, FundingPayment, OrderInfo, Rest, WalletDeposit};
+use chrono::{DateTime, Duration, SecondsFormat, SubsecRound, Utc};
+use serde::{de::DeserializeOwned, Serialize};
+use sqlx::sqlite::{SqliteConnectOptions, SqlitePool, SqlitePoolOptions};
+use std::{future::Future, path::Path};
+
+/// Records requested at a time while syncing.
+pub const DEFAULT_PAGE_SIZE: usize = 200;
+
+#[derive(Clone, Debug)]
+pub struct Archive {
+    pool: SqlitePool,
+    page_size: usize,
+}
+
+/// The number of records `Archive::sync` added to each table.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct SyncSummary {
+    pub fills: usize,
+    pub orders: usize,
+    pub deposits: usize,
+    pub funding_payments: usize,
+}
+
+impl Archive {
+    /// Opens the journal at `path`, creating it if it does not exist yet.
+    pub async fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let options = SqliteConnectOptions::new()
+            .filename(path)
+            .create_if_missing(true);
+        Self::with_pool(SqlitePool::connect_with(options).await?).await
+    }
+
+    /// A journal that is kept in memory and lost on drop.
+    pub async fn in_memory() -> Result<Self> {
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .idle_timeout(None)
+            .max_lifetime(None)
+            .connect("sqlite::memory:")
+            .await?;
+        Self::with_pool(pool).await
+    }
+
+    async fn with_pool(pool: SqlitePool) -> Result<Self> {
+        sqlx::migrate!("migrations/archive").run(&pool).await?;
+        Ok(Self {
+            pool,
+            page_size: DEFAULT_PAGE_SIZE,
+        })
+    }
+
+    /// Sets the number of records requested at a time while syncing.
+    pub fn with_page_size(mut self, page_size: usize) -> Self {
+        self.page_size = page_size.max(1);
+        self
+    }
+
+    pub fn pool(&self) -> &SqlitePool {
+        &self.pool
+    }
+
+    /// Fetches the records that are newer than the stored ones from `api`
+    /// and stores them. Each table is stored as soon as its records are
+    /// fetched, so an error keeps the tables synced before it.
+    pub async fn sync(&self, api: &Rest) -> Result<SyncSummary> {
+        let before = self.counts().await?;
+
+        let since = self
+            .first("SELECT time FROM fills ORDER BY time DESC")
+            .await?;
+        let fills = self
+            .fetch(
+                since,
+                |fill: &FillInfo| fill.time,
+                |limit, start, end| api.get_fills(None, Some(limit), None, start, end),
+            )
+            .await?;
+        let mut tx = self.pool.begin().await?;
+        for fill in &fills {
+            sqlx::query(
+                "INSERT INTO fills (id, market, time, data) VALUES (?, ?, ?, ?) \
+                 ON CONFLICT (id) DO UPDATE SET market = excluded.market, \
+                 time = excluded.time, data = excluded.data",
+            )
+            .bind(fill.id)
+            .bind(&fill.market)
+            .bind(timestamp(fill.time))
+            .bind(serde_json::to_string(fill)?)
+            .execute(&mut tx)
+            .await?;
+        }
+        tx.commit().await?;
+
+        let since = match self
+            .first("SELECT created_at FROM orders WHERE status != 'closed' ORDER BY created_at")
+            .await?
+        {
+            Some(time) => Some(time),
+            None => {
+                self.first("SELECT created_at FROM orders ORDER BY created_at DESC")
+                    .await?
+            }
+        };
+        let orders = self
+            .fetch(
+                since,
+                |order: &OrderInfo| order.created_at,
+                |limit, start, end| api.get_order_history(None, Some(limit), start, end),
+            )
+            .await?;
+        let mut tx = self.pool.begin().await?;
+        for order in &orders {
+            sqlx::query(
+                "INSERT INTO orders (id, market, status, created_at, data) VALUES (?, ?, ?, ?, ?) \
+                 ON CONFLICT (id) DO UPDATE SET market = excluded.market, \
+                 status = excluded.status, created_at = excluded.created_at, \
+                 data = excluded.data",
+            )
+            .bind(order.id)
+            .bind(&order.market)
+            .bind(name(&order.status)?)
+            .bind(timestamp(order.created_at))
+            .bind(serde_json::to_string(order)?)
+            .execute(&mut tx)
+            .await?;
+        }
+        tx.commit().await?;
+
+        let since = match self
+            .first(
+                "SELECT time FROM deposits \
+                 WHERE status NOT IN ('confirmed', 'complete', 'cancelled') ORDER BY time",
+            )
+            .await?
+        {
+            Some(time) => Some(time),
+            None => {
+                self.first("SELECT time FROM deposits ORDER BY time DESC")
+                    .await?
+            }
+        };
+        let deposits = self
+            .fetch(
+                since,
+                |deposit: &WalletDeposit| deposit.time,
+                |limit, start, end| api.get_wallet_deposits(Some(limit), start, end),
+            )
+            .await?;
+        let mut tx = self.pool.begin().await?;
+        for deposit in &deposits {
+            sqlx::query(
+                "INSERT INTO deposits (id, coin, status, time, data) VALUES (?, ?, ?, ?, ?) \
+                 ON CONFLICT (id) DO UPDATE SET coin = excluded.coin, \
+                 status = excluded.status, time = excluded.time, data = excluded.data",
+            )
+            .bind(deposit.id)
+            .bind(&deposit.coin)
+            .bind(name(&deposit.status)?)
+            .bind(timestamp(deposit.time))
+            .bind(serde_json::to_string(deposit)?)
+            .execute(&mut tx)
+            .await?;
+        }
+        tx.commit().await?;
+
+        let since = self
+            .first("SELECT time FROM funding_payments ORDER BY time DESC")
+            .await?;
+        let funding_payments = self
+            .fetch(
+                since,
+                |payment: &FundingPayment| payment.time,
+                |limit, start, end| api.get_funding_payments(None, Some(limit as u32), start, end),
+            )
+            .await?;
+        let mut tx = self.pool.begin().await?;
+        for payment in &funding_payments {
+            sqlx::query(
+                "INSERT INTO funding_payments (id, future, time, data) VALUES (?, ?, ?, ?) \
+                 ON CONFLICT (id) DO UPDATE SET future = excluded.future, \
+                 time = excluded.time, data = excluded.data",
+            )
+            .bind(payment.id)
+            .bind(&payment.future)
+            .bind(timestamp(payment.time))
+            .bind(serde_json::to_string(payment)?)
+            .execute(&mut tx)
+            .await?;
+        }
+        tx.commit().await?;
+        let after = self.counts().await?;
+
+        Ok(SyncSummary {
+            fills: after.fills - before.fills,
+            orders: after.orders - before.orders,
+            deposits: after.deposits - before.deposits,
+            funding_payments: after.funding_payments - before.funding_payments,
+        })
+    }
+
+    /// Stored fills, oldest first.
+    pub async fn fills(&self) -> Result<Vec<FillInfo>> {
+        self.records("SELECT data FROM fills ORDER BY time, id")
+            .await
+    }
+
+    /// Stored orders, oldest first.
+    pub async fn orders(&self) -> Result<Vec<OrderInfo>> {
+        self.records("SELECT data FROM orders ORDER BY created_at, id")
+            .await
+    }
+
+    /// Stored deposits, oldest first.
+    pub async fn deposits(&self) -> Result<Vec<WalletDeposit>> {
+        self.records("SELECT data FROM deposits ORDER BY time, id")
+            .await
+    }
+
+    /// Stored funding payments, oldest first.
+    pub async fn funding_payments(&self) -> Result<Vec<FundingPayment>> {
+        self.records("SELECT data FROM funding_payments ORDER BY time, id")
+            .await
+    }
+
+    /// Requests every record at or after `since`. FTX returns the most
+    /// recent records first, so older pages are requested by moving the end
+    /// of the window back to the oldest record received so far. A page that
+    /// holds nothing but one second cannot move the window, so it is
+    /// requested again with twice the limit until the rest of that second
+    /// fits.
+    async fn fetch<T, F, Fut>(
+        &self,
+        since: Option<DateTime<Utc>>,
+        time: fn(&T) -> DateTime<Utc>,
+        mut fetch: F,
+    ) -> Result<Vec<T>>
+    where
+        F: FnMut(usize, Option<DateTime<Utc>>, Option<DateTime<Utc>>) -> Fut,
+        Fut: Future<Output = crate::rest::Result<Vec<T>>>,
+    {
+        // Time windows have a resolution of seconds and are rounded when
+        // sent, so the window is widened to whole seconds.
+        let since = since.map(|since| since.trunc_subsecs(0));
+        let mut records = Vec::new();
+        let mut limit = self.page_size;
+        let mut end_time: Option<DateTime<Utc>> = None;
+        loop {
+            let page = fetch(limit, since, end_time).await?;
+            let full = page.len() >= limit;
+            let oldest = page.iter().map(time).min();
+            records.extend(page);
+
+            let oldest = match oldest {
+                Some(oldest) if full => oldest,
+                _ => break,
+            };
+            let next_end = oldest.trunc_subsecs(0) + Duration::seconds(1);
+            if matches!(end_time, Some(end) if next_end >= end) {
+                limit *= 2;
+            } else {
+                limit = self.page_size;
+                end_time = Some(next_end);
+            }
+        }
+        Ok(records)
+    }
+
+    async fn first(&self, query: &str) -> Result<Option<DateTime<Utc>>> {
+        let time: Option<String> = sqlx::query_scalar(&format!("{} LIMIT 1", query))
+            .fetch_optional(&self.pool)
+            .await?;
+        Ok(time
+            .and_then(|time| DateTime::parse_from_rfc3339(&time).ok())
+            .map(|time| time.with_timezone(&Utc)))
+    }
+
+    async fn records<T: DeserializeOwned>(&self, query: &str) -> Result<Vec<T>> {
+        let rows: Vec<String> = sqlx::query_scalar(query).fetch_all(&self.pool).await?;
+        Ok(rows
+            .iter()
+            .map(|data| serde_json::from_str(data))
+            .collect::<serde_json::Result<_>>()?)
+    }
+
+    async fn counts(&self) -> Result<SyncSummary> {
+        let (fills, orders, deposits, funding_payments): (i64, i64, i64, i64) = sqlx::query_as(
+            "SELECT (SELECT count(*) FROM fills), (SELECT count(*) FROM orders), \
+             (SELECT count(*) FROM deposits), (SELECT count(*) FROM funding_payments)",
+        )
+        .fetch_one(&self.pool)
+        .await?;
+        Ok(SyncSummary {
+            fills: fills as usize,
+            orders: orders as usize,
+            deposits: deposits as usize,
+            funding_payments: funding_payments as usize,
+        })
+    }
+}
+
+/// Timestamps are stored with a fixed precision, so that they sort by time.
+fn timestamp(time: DateTime<Utc>) -> String {
+    time.to_rfc3339_opts(SecondsFormat::Micros, true)
+}
+
+/// The name FTX uses for a status, e.g. `closed`.
+fn name<T: Serialize>(status: &T) -> Result<String> {
+    Ok(serde_json::to_value(status)?
+        .as_str()
+        .unwrap_or_default()
+        .to_string())
+}
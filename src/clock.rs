@@ -0,0 +1,80 @@
+//! This module keeps request timestamps in line with the exchange's clock.
+//!
+//! FTX rejects requests whose timestamp is too far from its own time with
+//! "Not logged in", so a drifting host clock breaks authentication. A `Clock`
+//! holds the measured offset to the server and applies it to every timestamp
+//! it hands out. Clones share the offset, so one `Clock` can be synchronized
+//! through `Rest::sync_time` and passed to `Ws::connect_with_clock`.
+
+use std::sync::{
+    atomic::{AtomicI64, Ordering},
+    Arc,
+};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+#[derive(Clone, Debug, Default)]
+pub struct Clock {
+    offset: Arc<AtomicI64>,
+}
+
+impl Clock {
+    /// A clock without any offset, i.e. the local time.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The estimated server time in milliseconds since the Unix epoch.
+    pub fn now(&self) -> u64 {
+        (local_millis() as i64 + self.offset()).max(0) as u64
+    }
+
+    /// Milliseconds the server clock is ahead of the local clock. Negative
+    /// when the local clock is ahead.
+    pub fn offset(&self) -> i64 {
+        self.offset.load(Ordering::Relaxed)
+    }
+
+    pub fn set_offset(&self, offset: i64) {
+        self.offset.store(offset, Ordering::Relaxed);
+    }
+
+    /// Updates the offset from a server time observed between `sent` and
+    /// `received` (local milliseconds since the epoch), assuming the server
+    /// read its clock halfway through the round trip. Returns the new offset.
+    pub fn update(&self, sent: u64, received: u64, server_time: u64) -> i64 {
+        let local_time = sent + received.saturating_sub(sent) / 2;
+        let offset = server_time as i64 - local_time as i64;
+        self.set_offset(offset);
+        offset
+    }
+}
+
+/// The local time in milliseconds since the Unix epoch.
+pub fn local_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_millis() as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn offset() {
+        let clock = Clock::new();
+        assert_eq!(0, clock.offset());
+
+        // Server 5s ahead, observed during a 200ms round trip
+        assert_eq!(5_000, clock.update(1_000_000, 1_000_200, 1_005_100));
+        assert_eq!(-1_000, clock.update(1_000_000, 1_000_000, 999_000));
+
+        // Clones share the offset
+        let shared = clock.clone();
+        shared.set_offset(60_000);
+        assert_eq!(60_000, clock.offset());
+        let now = clock.now();
+        assert!(now.abs_diff(local_millis() + 60_000) < 1_000);
+    }
+}
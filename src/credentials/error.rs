@@ -0,0 +1,28 @@
+use std::path::PathBuf;
+use thiserror::Error;
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("no credentials for account {0:?}")]
+    UnknownAccount(String),
+
+    #[error("environment variable {0} is not defined")]
+    MissingVar(String),
+
+    #[error("credentials file {path:?} is invalid: {source}")]
+    InvalidFile {
+        path: PathBuf,
+        source: toml::de::Error,
+    },
+
+    #[error(transparent)]
+    Rest(#[from] crate::rest::Error),
+
+    #[error(transparent)]
+    Toml(#[from] toml::de::Error),
+
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+}
@@ -0,0 +1,218 @@
+//! This module loads API credentials for one or more accounts from
+//! environment variables or a TOML file, and builds clients from them.
+//!
+//! A credentials file has one table per account:
+//!
+//! ```toml
+//! [main]
+//! key = "..."
+//! secret = "..."
+//!
+//! [bot]
+//! key = "..."
+//! secret = "..."
+//! subaccount = "Bot"
+//! proxy = "127.0.0.1:1080"
+//! ```
+
+mod error;
+#[cfg(test)]
+mod tests;
+
+pub use error::*;
+
+use crate::rest::Rest;
+#[cfg(feature = "ws")]
+use crate::ws::{self, Ws};
+use serde::Deserialize;
+use std::{collections::BTreeMap, fmt, fs, path::Path};
+use zeroize::Zeroizing;
+
+/// The name of the account read from the unprefixed `FTX_*` variables.
+pub const DEFAULT_ACCOUNT: &str = "default";
+
+/// An API key pair together with the subaccount and proxy to use it with.
+/// The secret is wiped from memory on drop and never printed by `Debug`.
+#[derive(Clone, Deserialize)]
+pub struct Credentials {
+    pub key: String,
+    #[serde(deserialize_with = "deserialize_secret")]
+    secret: Zeroizing<String>,
+    #[serde(default)]
+    pub subaccount: Option<String>,
+    /// Address of a SOCKS5 proxy, e.g. `127.0.0.1:1080`.
+    #[serde(default)]
+    pub proxy: Option<String>,
+}
+
+fn deserialize_secret<'de, D>(deserializer: D) -> std::result::Result<Zeroizing<String>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    String::deserialize(deserializer).map(Zeroizing::new)
+}
+
+impl Credentials {
+    pub fn new(key: String, secret: String) -> Self {
+        Self {
+            key,
+            secret: Zeroizing::new(secret),
+            subaccount: None,
+            proxy: None,
+        }
+    }
+
+    pub fn with_subaccount(mut self, subaccount: impl Into<String>) -> Self {
+        self.subaccount = Some(subaccount.into());
+        self
+    }
+
+    pub fn with_proxy(mut self, proxy: impl Into<String>) -> Self {
+        self.proxy = Some(proxy.into());
+        self
+    }
+
+    pub fn secret(&self) -> &str {
+        &self.secret
+    }
+
+    /// Reads `FTX_API_KEY`, `FTX_API_SECRET`, `FTX_SUBACCOUNT` and
+    /// `SOCKS_PROXY`, also from a `.env` file, as in `.env.example`.
+    pub fn from_env() -> Result<Self> {
+        dotenv::dotenv().ok();
+        from_vars(&std::env::vars().collect(), "FTX")
+    }
+
+    /// Reads `{prefix}_API_KEY`, `{prefix}_API_SECRET`, `{prefix}_SUBACCOUNT`
+    /// and `{prefix}_SOCKS_PROXY`, also from a `.env` file.
+    pub fn from_env_prefix(prefix: &str) -> Result<Self> {
+        dotenv::dotenv().ok();
+        from_vars(&std::env::vars().collect(), prefix)
+    }
+
+    /// Builds a REST client authenticated with these credentials.
+    pub fn rest(&self) -> crate::rest::Result<Rest> {
+        Rest::new(
+            self.key.clone(),
+            self.secret.to_string(),
+            self.subaccount.clone(),
+            self.proxy.clone(),
+        )
+    }
+
+    /// Connects an authenticated websocket with these credentials.
+    #[cfg(feature = "ws")]
+    pub async fn ws(&self) -> ws::Result<Ws> {
+        Ws::connect(
+            Some((self.key.clone(), self.secret.to_string())),
+            self.subaccount.clone(),
+            self.proxy.clone(),
+        )
+        .await
+    }
+}
+
+impl fmt::Debug for Credentials {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Credentials")
+            .field("key", &self.key)
+            .field("secret", &"<redacted>")
+            .field("subaccount", &self.subaccount)
+            .field("proxy", &self.proxy)
+            .finish()
+    }
+}
+
+fn from_vars(vars: &BTreeMap<String, String>, prefix: &str) -> Result<Credentials> {
+    let var = |name: &str| vars.get(&format!("{}_{}", prefix, name)).cloned();
+    let required =
+        |name: &str| var(name).ok_or_else(|| Error::MissingVar(format!("{}_{}", prefix, name)));
+
+    Ok(Credentials {
+        key: required("API_KEY")?,
+        secret: Zeroizing::new(required("API_SECRET")?),
+        subaccount: var("SUBACCOUNT"),
+        proxy: var("SOCKS_PROXY").or_else(|| match prefix {
+            "FTX" => vars.get("SOCKS_PROXY").cloned(),
+            _ => None,
+        }),
+    })
+}
+
+/// Named credentials of several accounts.
+#[derive(Clone, Debug, Default)]
+pub struct AccountRegistry {
+    accounts: BTreeMap<String, Credentials>,
+}
+
+impl AccountRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Loads every account defined in the environment, also from a `.env`
+    /// file. `FTX_API_KEY` and friends define the `default` account, and
+    /// `FTX_<NAME>_API_KEY` and friends the account `<name>` in lowercase.
+    pub fn from_env() -> Result<Self> {
+        dotenv::dotenv().ok();
+        Self::from_vars(&std::env::vars().collect())
+    }
+
+    fn from_vars(vars: &BTreeMap<String, String>) -> Result<Self> {
+        let mut registry = Self::new();
+        for var in vars.keys() {
+            let prefix = match var.strip_suffix("_API_KEY") {
+                Some(prefix) if prefix == "FTX" || prefix.starts_with("FTX_") => prefix,
+                _ => continue,
+            };
+            let name = match prefix.strip_prefix("FTX_") {
+                Some(name) => name.to_lowercase(),
+                None => DEFAULT_ACCOUNT.to_string(),
+            };
+            registry.insert(name, from_vars(vars, prefix)?);
+        }
+        Ok(registry)
+    }
+
+    /// Parses accounts from TOML, with one table per account.
+    pub fn from_toml(toml: &str) -> Result<Self> {
+        Ok(Self {
+            accounts: toml::from_str(toml)?,
+        })
+    }
+
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let toml = fs::read_to_string(path)?;
+        let accounts = toml::from_str(&toml).map_err(|source| Error::InvalidFile {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        Ok(Self { accounts })
+    }
+
+    pub fn insert(&mut self, name: impl Into<String>, credentials: Credentials) {
+        self.accounts.insert(name.into(), credentials);
+    }
+
+    pub fn get(&self, name: &str) -> Result<&Credentials> {
+        self.accounts
+            .get(name)
+            .ok_or_else(|| Error::UnknownAccount(name.to_string()))
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.accounts.keys().map(String::as_str)
+    }
+
+    /// Builds a REST client for the named account.
+    pub fn rest(&self, name: &str) -> Result<Rest> {
+        Ok(self.get(name)?.rest()?)
+    }
+
+    /// Connects an authenticated websocket for the named account.
+    #[cfg(feature = "ws")]
+    pub async fn ws(&self, name: &str) -> ws::Result<Ws> {
+        self.get(name)?.ws().await
+    }
+}
@@ -0,0 +1,83 @@
+use super::*;
+
+fn vars(vars: &[(&str, &str)]) -> BTreeMap<String, String> {
+    vars.iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect()
+}
+
+#[test]
+fn env_accounts() {
+    let vars = vars(&[
+        ("FTX_API_KEY", "key"),
+        ("FTX_API_SECRET", "secret"),
+        ("FTX_SUBACCOUNT", "Bot"),
+        ("SOCKS_PROXY", "127.0.0.1:1080"),
+        ("FTX_HEDGE_API_KEY", "hedge-key"),
+        ("FTX_HEDGE_API_SECRET", "hedge-secret"),
+        ("OTHER_API_KEY", "ignored"),
+    ]);
+
+    let registry = AccountRegistry::from_vars(&vars).unwrap();
+    assert_eq!(
+        vec!["default", "hedge"],
+        registry.names().collect::<Vec<_>>()
+    );
+
+    let default = registry.get(DEFAULT_ACCOUNT).unwrap();
+    assert_eq!("key", default.key);
+    assert_eq!("secret", default.secret());
+    assert_eq!(Some("Bot".to_string()), default.subaccount);
+    assert_eq!(Some("127.0.0.1:1080".to_string()), default.proxy);
+
+    // The unprefixed proxy only applies to the default account
+    let hedge = registry.get("hedge").unwrap();
+    assert_eq!("hedge-secret", hedge.secret());
+    assert_eq!(None, hedge.subaccount);
+    assert_eq!(None, hedge.proxy);
+
+    match registry.get("missing") {
+        Err(Error::UnknownAccount(name)) => assert_eq!("missing", name),
+        result => panic!("Unknown account error expected, got {:?}.", result),
+    }
+
+    let incomplete = self::vars(&[("FTX_API_KEY", "key")]);
+    match AccountRegistry::from_vars(&incomplete) {
+        Err(Error::MissingVar(var)) => assert_eq!("FTX_API_SECRET", var),
+        result => panic!("Missing variable error expected, got {:?}.", result),
+    }
+}
+
+#[test]
+fn toml_accounts() {
+    let registry = AccountRegistry::from_toml(
+        r#"
+        [main]
+        key = "main-key"
+        secret = "main-secret"
+
+        [bot]
+        key = "bot-key"
+        secret = "bot-secret"
+        subaccount = "Bot"
+        proxy = "127.0.0.1:1080"
+        "#,
+    )
+    .unwrap();
+
+    let main = registry.get("main").unwrap();
+    assert_eq!("main-secret", main.secret());
+    assert_eq!(None, main.subaccount);
+
+    let bot = registry.get("bot").unwrap();
+    assert_eq!(Some("Bot".to_string()), bot.subaccount);
+    assert_eq!(Some("Bot"), registry.rest("bot").unwrap().subaccount());
+
+    // Secrets never show up in debug output
+    let debug = format!("{:?}", registry);
+    assert!(debug.contains("bot-key"));
+    assert!(!debug.contains("main-secret"));
+    assert!(!debug.contains("bot-secret"));
+
+    AccountRegistry::from_toml("[main]\nkey = \"key\"").unwrap_err();
+}
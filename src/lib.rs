@@ -1,3 +1,12 @@
+#[cfg(feature = "archive")]
+pub mod archive;
+pub mod clock;
+pub mod credentials;
+#[cfg(feature = "mock")]
+pub mod mock;
 pub mod rest;
+pub mod signer;
+#[cfg(feature = "postgres")]
+pub mod store;
 #[cfg(feature = "ws")]
 pub mod ws;
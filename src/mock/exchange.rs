@@ -0,0 +1,1295 @@
+use crate::rest::{
+    DepositStatus, FundingPayment, Id, Liquidity, OrderInfo, OrderStatus, OrderType, Side, Symbol,
+    WalletDeposit,
+};
+use crate::signer::Signer;
+use chrono::{DateTime, TimeZone, Utc};
+use rust_decimal::prelude::*;
+use rust_decimal_macros::dec;
+use serde_json::{json, Map, Value};
+use std::collections::{BTreeMap, HashMap, VecDeque};
+
+/// Error message returned to the client, together with the HTTP status code.
+pub(crate) type Rejection = (u16, String);
+
+pub(crate) type Reply = std::result::Result<Value, Rejection>;
+
+fn reject<T>(message: &str) -> std::result::Result<T, Rejection> {
+    Err((400, message.to_string()))
+}
+
+fn not_found<T>(message: &str) -> std::result::Result<T, Rejection> {
+    Err((404, message.to_string()))
+}
+
+/// The account an order or balance belongs to, i.e. the subaccount
+/// nickname or `None` for the main account.
+pub(crate) type Owner = Option<String>;
+
+/// A market listed on the mock exchange.
+#[derive(Clone, Debug)]
+pub struct MockMarket {
+    pub name: Symbol,
+    /// `None` for futures markets.
+    pub base_currency: Option<String>,
+    /// `None` for futures markets.
+    pub quote_currency: Option<String>,
+    /// `None` for spot markets.
+    pub underlying: Option<String>,
+    pub price_increment: Decimal,
+    pub size_increment: Decimal,
+    pub min_provide_size: Decimal,
+}
+
+impl MockMarket {
+    pub fn spot(
+        base: &str,
+        quote: &str,
+        price_increment: Decimal,
+        size_increment: Decimal,
+    ) -> Self {
+        Self {
+            name: format!("{}/{}", base, quote),
+            base_currency: Some(base.to_string()),
+            quote_currency: Some(quote.to_string()),
+            underlying: None,
+            price_increment,
+            size_increment,
+            min_provide_size: size_increment,
+        }
+    }
+
+    pub fn future(
+        name: &str,
+        underlying: &str,
+        price_increment: Decimal,
+        size_increment: Decimal,
+    ) -> Self {
+        Self {
+            name: name.to_string(),
+            base_currency: None,
+            quote_currency: None,
+            underlying: Some(underlying.to_string()),
+            price_increment,
+            size_increment,
+            min_provide_size: size_increment,
+        }
+    }
+
+    fn is_spot(&self) -> bool {
+        self.underlying.is_none()
+    }
+}
+
+/// An entry resting in the book. Liquidity seeded with
+/// `MockServer::set_orderbook` does not belong to any order.
+#[derive(Clone, Debug)]
+struct Resting {
+    order_id: Option<Id>,
+    size: Decimal,
+}
+
+#[derive(Clone, Debug, Default)]
+struct Book {
+    bids: BTreeMap<Decimal, VecDeque<Resting>>,
+    asks: BTreeMap<Decimal, VecDeque<Resting>>,
+}
+
+impl Book {
+    fn side_mut(&mut self, side: Side) -> &mut BTreeMap<Decimal, VecDeque<Resting>> {
+        match side {
+            Side::Buy => &mut self.bids,
+            Side::Sell => &mut self.asks,
+        }
+    }
+
+    /// Best price on the side opposite to `side`, i.e. the price an order
+    /// with the given side would trade at first.
+    fn best_opposite(&self, side: Side) -> Option<Decimal> {
+        match side {
+            Side::Buy => self.asks.keys().next().copied(),
+            Side::Sell => self.bids.keys().next_back().copied(),
+        }
+    }
+
+    fn remove_order(&mut self, side: Side, price: Decimal, order_id: Id) {
+        let levels = self.side_mut(side);
+        if let Some(level) = levels.get_mut(&price) {
+            level.retain(|resting| resting.order_id != Some(order_id));
+            if level.is_empty() {
+                levels.remove(&price);
+            }
+        }
+    }
+
+    /// Aggregated levels in 'best' order, i.e. highest to lowest bids and
+    /// lowest to highest asks.
+    fn levels(&self, side: Side, depth: usize) -> Vec<(Decimal, Decimal)> {
+        let aggregate = |(price, level): (&Decimal, &VecDeque<Resting>)| {
+            (*price, level.iter().map(|r| r.size).sum())
+        };
+        match side {
+            Side::Buy => self.bids.iter().rev().take(depth).map(aggregate).collect(),
+            Side::Sell => self.asks.iter().take(depth).map(aggregate).collect(),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+struct Fill {
+    id: Id,
+    owner: Owner,
+    market: Symbol,
+    future: Option<Symbol>,
+    side: Side,
+    price: Decimal,
+    size: Decimal,
+    time: DateTime<Utc>,
+    fee: Decimal,
+    fee_currency: String,
+    fee_rate: Decimal,
+    liquidity: Liquidity,
+    base_currency: Option<String>,
+    quote_currency: Option<String>,
+    order_id: Id,
+    trade_id: Id,
+}
+
+#[derive(Clone, Debug, Default)]
+struct Position {
+    net_size: Decimal,
+    cost: Decimal,
+    realized_pnl: Decimal,
+}
+
+#[derive(Clone, Debug)]
+struct Subaccount {
+    nickname: String,
+    competition: bool,
+}
+
+/// The state of the mock exchange.
+#[derive(Debug)]
+pub(crate) struct Exchange {
+    pub(crate) api_keys: HashMap<String, Signer>,
+    pub(crate) maker_fee: Decimal,
+    pub(crate) taker_fee: Decimal,
+    /// Milliseconds the simulated server clock is ahead of the local clock.
+    pub(crate) clock_offset: i64,
+    markets: BTreeMap<Symbol, MockMarket>,
+    books: HashMap<Symbol, Book>,
+    last_prices: HashMap<Symbol, Decimal>,
+    orders: BTreeMap<Id, (Owner, OrderInfo)>,
+    fills: Vec<Fill>,
+    deposits: Vec<(Owner, WalletDeposit)>,
+    funding_payments: Vec<(Owner, FundingPayment)>,
+    balances: HashMap<Owner, BTreeMap<String, Decimal>>,
+    positions: HashMap<Owner, BTreeMap<Symbol, Position>>,
+    subaccounts: Vec<Subaccount>,
+    next_id: Id,
+}
+
+impl Default for Exchange {
+    fn default() -> Self {
+        Self {
+            api_keys: HashMap::new(),
+            // Fees of the lowest FTX tier
+            maker_fee: dec!(0.0002),
+            taker_fee: dec!(0.0007),
+            clock_offset: 0,
+            markets: BTreeMap::new(),
+            books: HashMap::new(),
+            last_prices: HashMap::new(),
+            orders: BTreeMap::new(),
+            fills: Vec::new(),
+            deposits: Vec::new(),
+            funding_payments: Vec::new(),
+            balances: HashMap::new(),
+            positions: HashMap::new(),
+            subaccounts: Vec::new(),
+            next_id: 1,
+        }
+    }
+}
+
+fn param<'a>(params: &'a Map<String, Value>, key: &str) -> Option<&'a Value> {
+    params.get(key).filter(|value| !value.is_null())
+}
+
+fn str_param<'a>(params: &'a Map<String, Value>, key: &str) -> Option<&'a str> {
+    param(params, key).and_then(Value::as_str)
+}
+
+fn bool_param(params: &Map<String, Value>, key: &str) -> std::result::Result<bool, Rejection> {
+    match param(params, key) {
+        None => Ok(false),
+        Some(Value::Bool(value)) => Ok(*value),
+        Some(Value::String(value)) if value == "true" || value == "false" => Ok(value == "true"),
+        Some(_) => reject(&format!("Invalid parameter {}", key)),
+    }
+}
+
+fn decimal_param(
+    params: &Map<String, Value>,
+    key: &str,
+) -> std::result::Result<Option<Decimal>, Rejection> {
+    match param(params, key) {
+        None => Ok(None),
+        Some(Value::String(value)) => match Decimal::from_str(value) {
+            Ok(value) => Ok(Some(value)),
+            Err(_) => reject(&format!("Invalid parameter {}", key)),
+        },
+        Some(Value::Number(value)) => match Decimal::from_str(&value.to_string())
+            .or_else(|_| Decimal::from_scientific(&value.to_string()))
+        {
+            Ok(value) => Ok(Some(value)),
+            Err(_) => reject(&format!("Invalid parameter {}", key)),
+        },
+        Some(_) => reject(&format!("Invalid parameter {}", key)),
+    }
+}
+
+fn time_param(
+    params: &Map<String, Value>,
+    key: &str,
+) -> std::result::Result<Option<DateTime<Utc>>, Rejection> {
+    Ok(decimal_param(params, key)?
+        .and_then(|seconds| seconds.to_i64())
+        .and_then(|seconds| Utc.timestamp_opt(seconds, 0).single()))
+}
+
+fn limit_param(params: &Map<String, Value>) -> std::result::Result<usize, Rejection> {
+    Ok(decimal_param(params, "limit")?
+        .and_then(|limit| limit.to_usize())
+        .unwrap_or(usize::MAX))
+}
+
+fn within(
+    time: DateTime<Utc>,
+    start_time: Option<DateTime<Utc>>,
+    end_time: Option<DateTime<Utc>>,
+) -> bool {
+    start_time.is_none_or(|start| time >= start) && end_time.is_none_or(|end| time <= end)
+}
+
+fn is_multiple(value: Decimal, increment: Decimal) -> bool {
+    increment.is_zero() || (value % increment).is_zero()
+}
+
+impl Exchange {
+    fn next_id(&mut self) -> Id {
+        let id = self.next_id;
+        self.next_id += 1;
+        id
+    }
+
+    pub(crate) fn add_market(&mut self, market: MockMarket) {
+        self.books.entry(market.name.clone()).or_default();
+        self.markets.insert(market.name.clone(), market);
+    }
+
+    /// Replaces the seeded liquidity of a market, keeping resting orders.
+    pub(crate) fn set_orderbook(
+        &mut self,
+        market: &str,
+        bids: &[(Decimal, Decimal)],
+        asks: &[(Decimal, Decimal)],
+    ) {
+        let book = self.books.entry(market.to_string()).or_default();
+        for levels in [&mut book.bids, &mut book.asks] {
+            for level in levels.values_mut() {
+                level.retain(|resting| resting.order_id.is_some());
+            }
+            levels.retain(|_, level| !level.is_empty());
+        }
+        for (side, levels) in [(Side::Buy, bids), (Side::Sell, asks)] {
+            for (price, size) in levels {
+                book.side_mut(side)
+                    .entry(*price)
+                    .or_default()
+                    .push_back(Resting {
+                        order_id: None,
+                        size: *size,
+                    });
+            }
+        }
+    }
+
+    pub(crate) fn deposit(&mut self, owner: Owner, coin: &str, size: Decimal) {
+        *self
+            .balances
+            .entry(owner)
+            .or_default()
+            .entry(coin.to_string())
+            .or_default() += size;
+    }
+
+    /// Credits an external deposit and records it in the deposit history.
+    pub(crate) fn record_deposit(&mut self, owner: Owner, coin: &str, size: Decimal) {
+        self.deposit(owner.clone(), coin, size);
+        let (id, time) = (self.next_id(), Utc::now());
+        self.deposits.push((
+            owner,
+            WalletDeposit {
+                id,
+                coin: coin.to_string(),
+                size,
+                time,
+                status: DepositStatus::Confirmed,
+                confirmations: None,
+                confirmed_time: Some(time),
+                fee: Some(Decimal::zero()),
+                txid: None,
+                notes: None,
+            },
+        ));
+    }
+
+    /// Records a funding payment and debits it from the USD balance. Negative
+    /// payments are funding received.
+    pub(crate) fn add_funding_payment(
+        &mut self,
+        owner: Owner,
+        future: &str,
+        payment: Decimal,
+        time: DateTime<Utc>,
+    ) {
+        self.deposit(owner.clone(), "USD", -payment);
+        let id = self.next_id();
+        self.funding_payments.push((
+            owner,
+            FundingPayment {
+                id,
+                future: future.to_string(),
+                payment,
+                time,
+            },
+        ));
+    }
+
+    pub(crate) fn has_subaccount(&self, nickname: &str) -> bool {
+        self.subaccounts.iter().any(|s| s.nickname == nickname)
+    }
+
+    fn market(&self, name: &str) -> std::result::Result<&MockMarket, Rejection> {
+        match self.markets.get(name) {
+            Some(market) => Ok(market),
+            None => not_found(&format!("No such market: {}", name)),
+        }
+    }
+
+    // Markets
+
+    fn market_json(&self, market: &MockMarket) -> Value {
+        let book = &self.books[&market.name];
+        let bid = book.best_opposite(Side::Sell);
+        let ask = book.best_opposite(Side::Buy);
+        let price = self
+            .last_prices
+            .get(&market.name)
+            .copied()
+            .or(bid)
+            .or(ask)
+            .unwrap_or_default();
+        json!({
+            "name": market.name,
+            "type": if market.is_spot() { "spot" } else { "future" },
+            "underlying": market.underlying,
+            "baseCurrency": market.base_currency,
+            "quoteCurrency": market.quote_currency,
+            "enabled": true,
+            "ask": ask.unwrap_or(price),
+            "bid": bid.unwrap_or(price),
+            "last": price,
+            "postOnly": false,
+            "priceIncrement": market.price_increment,
+            "sizeIncrement": market.size_increment,
+            "restricted": false,
+            "minProvideSize": market.min_provide_size,
+            "price": price,
+            "highLeverageFeeExempt": false,
+            "change1h": 0,
+            "change24h": 0,
+            "changeBod": 0,
+            "quoteVolume24h": 0,
+            "volumeUsd24h": 0,
+        })
+    }
+
+    pub(crate) fn get_markets(&self) -> Reply {
+        Ok(self
+            .markets
+            .values()
+            .map(|market| self.market_json(market))
+            .collect())
+    }
+
+    pub(crate) fn get_market(&self, name: &str) -> Reply {
+        let market = self.market(name)?;
+        Ok(self.market_json(market))
+    }
+
+    pub(crate) fn get_orderbook(&self, name: &str, params: &Map<String, Value>) -> Reply {
+        self.market(name)?;
+        let depth = decimal_param(params, "depth")?
+            .and_then(|depth| depth.to_usize())
+            .unwrap_or(20)
+            .min(100);
+        let book = &self.books[name];
+        Ok(json!({
+            "bids": book.levels(Side::Buy, depth),
+            "asks": book.levels(Side::Sell, depth),
+        }))
+    }
+
+    // Orders
+
+    pub(crate) fn place_order(&mut self, owner: &Owner, params: &Map<String, Value>) -> Reply {
+        let market = match str_param(params, "market") {
+            Some(market) => self.market(market)?.clone(),
+            None => return reject("Missing parameter market"),
+        };
+        let side = match str_param(params, "side") {
+            Some("buy") => Side::Buy,
+            Some("sell") => Side::Sell,
+            _ => return reject("Invalid parameter side"),
+        };
+        let r#type = match str_param(params, "type") {
+            Some("limit") => OrderType::Limit,
+            Some("market") => OrderType::Market,
+            _ => return reject("Invalid parameter type"),
+        };
+        let price = decimal_param(params, "price")?;
+        let size = match decimal_param(params, "size")? {
+            Some(size) => size,
+            None => return reject("Missing parameter size"),
+        };
+        let client_id = str_param(params, "clientId").map(str::to_string);
+
+        self.submit_order(
+            owner,
+            &market,
+            side,
+            r#type,
+            price,
+            size,
+            bool_param(params, "reduceOnly")?,
+            bool_param(params, "ioc")?,
+            bool_param(params, "postOnly")?,
+            client_id,
+        )
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn submit_order(
+        &mut self,
+        owner: &Owner,
+        market: &MockMarket,
+        side: Side,
+        r#type: OrderType,
+        price: Option<Decimal>,
+        size: Decimal,
+        reduce_only: bool,
+        ioc: bool,
+        post_only: bool,
+        client_id: Option<String>,
+    ) -> Reply {
+        let price = match (r#type, price) {
+            (OrderType::Limit, None) => return reject("Missing parameter price"),
+            (OrderType::Limit, Some(price)) if price <= Decimal::zero() => {
+                return reject("Invalid price")
+            }
+            (OrderType::Limit, Some(price)) if !is_multiple(price, market.price_increment) => {
+                return reject("Invalid price")
+            }
+            (OrderType::Limit, price) => price,
+            (OrderType::Market, _) => None,
+        };
+        if size <= Decimal::zero() || !is_multiple(size, market.size_increment) {
+            return reject("Invalid size");
+        }
+        if r#type == OrderType::Limit && !ioc && size < market.min_provide_size {
+            return reject("Size too small for provide");
+        }
+        if let Some(client_id) = &client_id {
+            let duplicate = self.orders.values().any(|(o, order)| {
+                o == owner
+                    && order.client_id.as_ref() == Some(client_id)
+                    && order.status != OrderStatus::Closed
+            });
+            if duplicate {
+                return reject("Duplicate client order ID");
+            }
+        }
+
+        let id = self.next_id();
+        let order = OrderInfo {
+            id,
+            market: market.name.clone(),
+            future: market.underlying.as_ref().map(|_| market.name.clone()),
+            r#type,
+            side,
+            price,
+            size,
+            reduce_only,
+            ioc,
+            post_only,
+            status: OrderStatus::New,
+            filled_size: Decimal::zero(),
+            remaining_size: size,
+            avg_fill_price: None,
+            liquidation: Some(false),
+            created_at: Utc::now(),
+            client_id,
+        };
+        // Orders are accepted before being processed, see `OrderStatus`
+        let accepted = serde_json::to_value(&order).unwrap_or_default();
+        self.orders.insert(id, (owner.clone(), order));
+        self.process_order(market, id);
+
+        Ok(accepted)
+    }
+
+    /// Matches a newly accepted order against the book and rests the
+    /// remainder if applicable.
+    fn process_order(&mut self, market: &MockMarket, id: Id) {
+        let (owner, order) = self.orders[&id].clone();
+        let book = self
+            .books
+            .get_mut(&market.name)
+            .expect("book of listed market");
+        let crosses = |best: Decimal| match (order.side, order.price) {
+            (_, None) => true,
+            (Side::Buy, Some(price)) => best <= price,
+            (Side::Sell, Some(price)) => best >= price,
+        };
+
+        if order.post_only && book.best_opposite(order.side).is_some_and(crosses) {
+            // Post-only orders that would take liquidity are rejected
+            self.orders.get_mut(&id).unwrap().1.status = OrderStatus::Closed;
+            return;
+        }
+
+        let mut remaining = order.size;
+        let mut matches = Vec::new();
+        while remaining > Decimal::zero() {
+            let best = match book.best_opposite(order.side) {
+                Some(best) if crosses(best) => best,
+                _ => break,
+            };
+            let opposite = book.side_mut(match order.side {
+                Side::Buy => Side::Sell,
+                Side::Sell => Side::Buy,
+            });
+            let level = opposite.get_mut(&best).expect("best level exists");
+            let resting = level.front_mut().expect("levels are never empty");
+            let size = resting.size.min(remaining);
+            resting.size -= size;
+            remaining -= size;
+            matches.push((resting.order_id, best, size));
+            if resting.size.is_zero() {
+                level.pop_front();
+                if level.is_empty() {
+                    opposite.remove(&best);
+                }
+            }
+        }
+
+        let trade_id = self.next_id;
+        for (maker_order_id, price, size) in matches {
+            self.record_fill(market, &owner, id, order.side, price, size, false, trade_id);
+            if let Some(maker_order_id) = maker_order_id {
+                let maker = self.orders[&maker_order_id].0.clone();
+                let maker_side = self.orders[&maker_order_id].1.side;
+                self.record_fill(
+                    market,
+                    &maker,
+                    maker_order_id,
+                    maker_side,
+                    price,
+                    size,
+                    true,
+                    trade_id,
+                );
+            }
+            self.last_prices.insert(market.name.clone(), price);
+        }
+        self.next_id += 1;
+
+        let (_, order) = self.orders.get_mut(&id).unwrap();
+        if remaining > Decimal::zero() && order.r#type == OrderType::Limit && !order.ioc {
+            order.status = OrderStatus::Open;
+            let (side, price) = (order.side, order.price.expect("limit orders have a price"));
+            self.books
+                .get_mut(&market.name)
+                .unwrap()
+                .side_mut(side)
+                .entry(price)
+                .or_default()
+                .push_back(Resting {
+                    order_id: Some(id),
+                    size: remaining,
+                });
+        } else {
+            order.status = OrderStatus::Closed;
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn record_fill(
+        &mut self,
+        market: &MockMarket,
+        owner: &Owner,
+        order_id: Id,
+        side: Side,
+        price: Decimal,
+        size: Decimal,
+        maker: bool,
+        trade_id: Id,
+    ) {
+        let fee_rate = if maker {
+            self.maker_fee
+        } else {
+            self.taker_fee
+        };
+        let notional = price * size;
+        let fee = notional * fee_rate;
+        let fee_currency = market
+            .quote_currency
+            .clone()
+            .unwrap_or_else(|| "USD".to_string());
+        let signed_size = match side {
+            Side::Buy => size,
+            Side::Sell => -size,
+        };
+
+        let (_, order) = self.orders.get_mut(&order_id).unwrap();
+        let filled = order.filled_size + size;
+        let avg_fill_price =
+            (order.avg_fill_price.unwrap_or_default() * order.filled_size + notional) / filled;
+        order.filled_size = filled;
+        order.remaining_size -= size;
+        order.avg_fill_price = Some(avg_fill_price);
+        if maker && order.remaining_size.is_zero() {
+            order.status = OrderStatus::Closed;
+        }
+
+        if let Some(base) = &market.base_currency {
+            self.deposit(owner.clone(), base, signed_size);
+            self.deposit(owner.clone(), &fee_currency, -signed_size * price - fee);
+        } else {
+            self.deposit(owner.clone(), &fee_currency, -fee);
+            let position = self
+                .positions
+                .entry(owner.clone())
+                .or_default()
+                .entry(market.name.clone())
+                .or_default();
+            let closing = !position.net_size.is_zero()
+                && position.net_size.is_sign_positive() != signed_size.is_sign_positive();
+            if closing {
+                let closed = signed_size.abs().min(position.net_size.abs());
+                let entry_price = position.cost / position.net_size;
+                let pnl = (price - entry_price) * closed * position.net_size.signum();
+                position.cost -= entry_price * closed * position.net_size.signum();
+                position.net_size -= closed * position.net_size.signum();
+                position.realized_pnl += pnl;
+                let opened = signed_size.abs() - closed;
+                position.net_size += opened * signed_size.signum();
+                position.cost += price * opened * signed_size.signum();
+                self.deposit(owner.clone(), &fee_currency, pnl);
+            } else {
+                position.net_size += signed_size;
+                position.cost += price * signed_size;
+            }
+        }
+
+        let id = self.next_id();
+        self.fills.push(Fill {
+            id,
+            owner: owner.clone(),
+            market: market.name.clone(),
+            future: market.underlying.as_ref().map(|_| market.name.clone()),
+            side,
+            price,
+            size,
+            time: Utc::now(),
+            fee,
+            fee_currency,
+            fee_rate,
+            liquidity: if maker {
+                Liquidity::Maker
+            } else {
+                Liquidity::Taker
+            },
+            base_currency: market.base_currency.clone(),
+            quote_currency: market.quote_currency.clone(),
+            order_id,
+            trade_id,
+        });
+    }
+
+    fn order_of(&self, owner: &Owner, id: Id) -> std::result::Result<&OrderInfo, Rejection> {
+        match self.orders.get(&id) {
+            Some((o, order)) if o == owner => Ok(order),
+            _ => not_found("Order not found"),
+        }
+    }
+
+    fn order_by_client_id(
+        &self,
+        owner: &Owner,
+        client_id: &str,
+    ) -> std::result::Result<&OrderInfo, Rejection> {
+        // The most recent order with the client ID takes precedence
+        let order = self
+            .orders
+            .values()
+            .rev()
+            .find(|(o, order)| o == owner && order.client_id.as_deref() == Some(client_id));
+        match order {
+            Some((_, order)) => Ok(order),
+            None => not_found("Order not found"),
+        }
+    }
+
+    pub(crate) fn get_order(&self, owner: &Owner, id: Id) -> Reply {
+        Ok(json!(self.order_of(owner, id)?))
+    }
+
+    pub(crate) fn get_order_by_client_id(&self, owner: &Owner, client_id: &str) -> Reply {
+        Ok(json!(self.order_by_client_id(owner, client_id)?))
+    }
+
+    pub(crate) fn get_open_orders(&self, owner: &Owner, params: &Map<String, Value>) -> Reply {
+        let market = str_param(params, "market");
+        Ok(self
+            .orders
+            .values()
+            .filter(|(o, order)| {
+                o == owner
+                    && order.status != OrderStatus::Closed
+                    && market.is_none_or(|market| order.market == market)
+            })
+            .map(|(_, order)| json!(order))
+            .collect())
+    }
+
+    pub(crate) fn get_order_history(&self, owner: &Owner, params: &Map<String, Value>) -> Reply {
+        let market = str_param(params, "market");
+        let start_time = time_param(params, "start_time")?;
+        let end_time = time_param(params, "end_time")?;
+        Ok(self
+            .orders
+            .values()
+            .rev()
+            .filter(|(o, order)| {
+                o == owner
+                    && market.is_none_or(|market| order.market == market)
+                    && within(order.created_at, start_time, end_time)
+            })
+            .take(limit_param(params)?)
+            .map(|(_, order)| json!(order))
+            .collect())
+    }
+
+    fn cancel(&mut self, id: Id) {
+        let (_, order) = self.orders.get_mut(&id).unwrap();
+        if order.status == OrderStatus::Closed {
+            return;
+        }
+        order.status = OrderStatus::Closed;
+        let (market, side, price) = (order.market.clone(), order.side, order.price);
+        if let (Some(book), Some(price)) = (self.books.get_mut(&market), price) {
+            book.remove_order(side, price, id);
+        }
+    }
+
+    pub(crate) fn cancel_order(&mut self, owner: &Owner, id: Id) -> Reply {
+        if self.order_of(owner, id)?.status == OrderStatus::Closed {
+            return reject("Order already closed");
+        }
+        self.cancel(id);
+        Ok(json!("Order queued for cancellation"))
+    }
+
+    pub(crate) fn cancel_order_by_client_id(&mut self, owner: &Owner, client_id: &str) -> Reply {
+        let id = self.order_by_client_id(owner, client_id)?.id;
+        self.cancel_order(owner, id)
+    }
+
+    pub(crate) fn cancel_all_orders(
+        &mut self,
+        owner: &Owner,
+        params: &Map<String, Value>,
+    ) -> Reply {
+        let market = str_param(params, "market");
+        let side = match str_param(params, "side") {
+            None => None,
+            Some("buy") => Some(Side::Buy),
+            Some("sell") => Some(Side::Sell),
+            Some(_) => return reject("Invalid parameter side"),
+        };
+        // The mock exchange has no conditional orders, so there is nothing
+        // to cancel if only those are requested
+        if !bool_param(params, "conditionalOrdersOnly")? {
+            let ids: Vec<Id> = self
+                .orders
+                .values()
+                .filter(|(o, order)| {
+                    o == owner
+                        && order.status != OrderStatus::Closed
+                        && market.is_none_or(|market| order.market == market)
+                        && side.is_none_or(|side| order.side == side)
+                })
+                .map(|(_, order)| order.id)
+                .collect();
+            for id in ids {
+                self.cancel(id);
+            }
+        }
+        Ok(json!("Orders queued for cancellation"))
+    }
+
+    /// Modifies an order by cancelling it and placing a new one, as FTX does.
+    fn modify(&mut self, owner: &Owner, id: Id, params: &Map<String, Value>) -> Reply {
+        let order = self.order_of(owner, id)?.clone();
+        if order.status == OrderStatus::Closed {
+            return reject("Order already closed");
+        }
+        let price = decimal_param(params, "price")?.or(order.price);
+        let size = decimal_param(params, "size")?.unwrap_or(order.remaining_size);
+        let client_id = str_param(params, "clientId")
+            .map(str::to_string)
+            .or_else(|| order.client_id.clone());
+        let market = self.market(&order.market)?.clone();
+
+        self.cancel(id);
+        self.submit_order(
+            owner,
+            &market,
+            order.side,
+            order.r#type,
+            price,
+            size,
+            order.reduce_only,
+            order.ioc,
+            order.post_only,
+            client_id,
+        )
+    }
+
+    pub(crate) fn modify_order(
+        &mut self,
+        owner: &Owner,
+        id: Id,
+        params: &Map<String, Value>,
+    ) -> Reply {
+        self.modify(owner, id, params)
+    }
+
+    pub(crate) fn modify_order_by_client_id(
+        &mut self,
+        owner: &Owner,
+        client_id: &str,
+        params: &Map<String, Value>,
+    ) -> Reply {
+        let id = self.order_by_client_id(owner, client_id)?.id;
+        self.modify(owner, id, params)
+    }
+
+    // Fills
+
+    pub(crate) fn get_fills(&self, owner: &Owner, params: &Map<String, Value>) -> Reply {
+        let market = str_param(params, "market");
+        let order_id = decimal_param(params, "orderId")?.and_then(|id| id.to_i64());
+        let start_time = time_param(params, "start_time")?;
+        let end_time = time_param(params, "end_time")?;
+        Ok(self
+            .fills
+            .iter()
+            .rev()
+            .filter(|fill| {
+                &fill.owner == owner
+                    && market.is_none_or(|market| fill.market == market)
+                    && order_id.is_none_or(|order_id| fill.order_id == order_id)
+                    && within(fill.time, start_time, end_time)
+            })
+            .take(limit_param(params)?)
+            .map(|fill| {
+                json!({
+                    "id": fill.id,
+                    "market": fill.market,
+                    "future": fill.future,
+                    "type": "order",
+                    "side": fill.side,
+                    "price": fill.price,
+                    "size": fill.size,
+                    "time": fill.time,
+                    "fee": fill.fee,
+                    "feeCurrency": fill.fee_currency,
+                    "feeRate": fill.fee_rate,
+                    "liquidity": fill.liquidity,
+                    "baseCurrency": fill.base_currency,
+                    "quoteCurrency": fill.quote_currency,
+                    "orderId": fill.order_id,
+                    "tradeId": fill.trade_id,
+                })
+            })
+            .collect())
+    }
+
+    // Account
+
+    fn positions_json(&self, owner: &Owner) -> Vec<Value> {
+        let positions = match self.positions.get(owner) {
+            Some(positions) => positions,
+            None => return Vec::new(),
+        };
+        positions
+            .iter()
+            .map(|(future, position)| {
+                let open_orders = |side| {
+                    self.orders
+                        .values()
+                        .filter(|(o, order)| {
+                            o == owner
+                                && &order.market == future
+                                && order.side == side
+                                && order.status != OrderStatus::Closed
+                        })
+                        .map(|(_, order)| order.remaining_size)
+                        .sum::<Decimal>()
+                };
+                let entry_price = if position.net_size.is_zero() {
+                    None
+                } else {
+                    Some(position.cost / position.net_size)
+                };
+                let mark = self.last_prices.get(future).copied();
+                let unrealized_pnl = match (entry_price, mark) {
+                    (Some(entry_price), Some(mark)) => (mark - entry_price) * position.net_size,
+                    _ => Decimal::zero(),
+                };
+                json!({
+                    "cost": position.cost,
+                    "entryPrice": entry_price,
+                    "estimatedLiquidationPrice": null,
+                    "future": future,
+                    "initialMarginRequirement": dec!(0.05),
+                    "longOrderSize": open_orders(Side::Buy),
+                    "maintenanceMarginRequirement": dec!(0.03),
+                    "netSize": position.net_size,
+                    "openSize": position.net_size.abs(),
+                    "realizedPnl": position.realized_pnl,
+                    "shortOrderSize": open_orders(Side::Sell),
+                    "side": if position.net_size.is_sign_negative() { "sell" } else { "buy" },
+                    "size": position.net_size.abs(),
+                    "unrealizedPnl": unrealized_pnl,
+                    "collateralUsed": position.cost.abs() * dec!(0.05),
+                })
+            })
+            .collect()
+    }
+
+    pub(crate) fn get_account(&self, owner: &Owner) -> Reply {
+        let collateral = self
+            .balances
+            .get(owner)
+            .and_then(|balances| balances.get("USD"))
+            .copied()
+            .unwrap_or_default();
+        let position_size: Decimal = self
+            .positions
+            .get(owner)
+            .map(|positions| positions.values().map(|p| p.cost.abs()).sum())
+            .unwrap_or_default();
+        let margin_fraction = if position_size.is_zero() {
+            None
+        } else {
+            Some(collateral / position_size)
+        };
+        Ok(json!({
+            "backstopProvider": false,
+            "chargeInterestOnNegativeUsd": false,
+            "collateral": collateral,
+            "freeCollateral": collateral - position_size * dec!(0.05),
+            "initialMarginRequirement": dec!(0.05),
+            "leverage": dec!(20),
+            "liquidating": false,
+            "maintenanceMarginRequirement": dec!(0.03),
+            "makerFee": self.maker_fee,
+            "marginFraction": margin_fraction,
+            "openMarginFraction": margin_fraction,
+            "positionLimit": null,
+            "positionLimitUsed": null,
+            "positions": self.positions_json(owner),
+            "spotLendingEnabled": false,
+            "spotMarginEnabled": false,
+            "takerFee": self.taker_fee,
+            "totalAccountValue": collateral,
+            "totalPositionSize": position_size,
+            "useFttCollateral": true,
+            "username": match owner {
+                Some(nickname) => format!("mock@example.com/{}", nickname),
+                None => "mock@example.com".to_string(),
+            },
+        }))
+    }
+
+    pub(crate) fn get_positions(&self, owner: &Owner) -> Reply {
+        Ok(self.positions_json(owner).into())
+    }
+
+    pub(crate) fn get_funding_payments(&self, owner: &Owner, params: &Map<String, Value>) -> Reply {
+        let future = str_param(params, "future");
+        let start_time = time_param(params, "start_time")?;
+        let end_time = time_param(params, "end_time")?;
+        let mut payments: Vec<_> = self
+            .funding_payments
+            .iter()
+            .filter(|(o, payment)| {
+                o == owner
+                    && future.is_none_or(|future| payment.future == future)
+                    && within(payment.time, start_time, end_time)
+            })
+            .map(|(_, payment)| payment)
+            .collect();
+        payments.sort_by_key(|payment| std::cmp::Reverse(payment.time));
+        Ok(json!(payments
+            .into_iter()
+            .take(limit_param(params)?)
+            .collect::<Vec<_>>()))
+    }
+
+    // Wallet
+
+    pub(crate) fn get_deposits(&self, owner: &Owner, params: &Map<String, Value>) -> Reply {
+        let start_time = time_param(params, "start_time")?;
+        let end_time = time_param(params, "end_time")?;
+        Ok(self
+            .deposits
+            .iter()
+            .rev()
+            .filter(|(o, deposit)| o == owner && within(deposit.time, start_time, end_time))
+            .take(limit_param(params)?)
+            .map(|(_, deposit)| json!(deposit))
+            .collect())
+    }
+
+    pub(crate) fn get_balances(&self, owner: &Owner) -> Reply {
+        let balances = match self.balances.get(owner) {
+            Some(balances) => balances,
+            None => return Ok(json!([])),
+        };
+        Ok(balances
+            .iter()
+            .map(|(coin, total)| {
+                let usd_value = if coin == "USD" {
+                    Some(*total)
+                } else {
+                    self.last_prices
+                        .get(&format!("{}/USD", coin))
+                        .map(|price| price * total)
+                };
+                json!({
+                    "coin": coin,
+                    "free": total,
+                    "total": total,
+                    "spotBorrow": 0,
+                    "availableWithoutBorrow": total,
+                    "usdValue": usd_value,
+                })
+            })
+            .collect())
+    }
+
+    // Subaccounts
+
+    fn main_account_only(owner: &Owner) -> std::result::Result<(), Rejection> {
+        match owner {
+            Some(_) => reject("Only main account can manage subaccounts"),
+            None => Ok(()),
+        }
+    }
+
+    pub(crate) fn get_subaccounts(&self, owner: &Owner) -> Reply {
+        Self::main_account_only(owner)?;
+        Ok(self
+            .subaccounts
+            .iter()
+            .map(|subaccount| {
+                json!({
+                    "nickname": subaccount.nickname,
+                    "deletable": true,
+                    "editable": true,
+                    "competition": subaccount.competition,
+                })
+            })
+            .collect())
+    }
+
+    pub(crate) fn create_subaccount(
+        &mut self,
+        owner: &Owner,
+        params: &Map<String, Value>,
+    ) -> Reply {
+        Self::main_account_only(owner)?;
+        let nickname = match str_param(params, "nickname") {
+            Some(nickname) if !nickname.is_empty() => nickname.to_string(),
+            _ => return reject("Missing parameter nickname"),
+        };
+        if self.has_subaccount(&nickname) {
+            return reject("Subaccount already exists");
+        }
+        self.subaccounts.push(Subaccount {
+            nickname: nickname.clone(),
+            competition: false,
+        });
+        Ok(json!({
+            "nickname": nickname,
+            "deletable": true,
+            "editable": true,
+        }))
+    }
+
+    pub(crate) fn change_subaccount_name(
+        &mut self,
+        owner: &Owner,
+        params: &Map<String, Value>,
+    ) -> Reply {
+        Self::main_account_only(owner)?;
+        let (nickname, new_nickname) = match (
+            str_param(params, "nickname"),
+            str_param(params, "newNickname"),
+        ) {
+            (Some(nickname), Some(new_nickname)) => (nickname, new_nickname.to_string()),
+            _ => return reject("Missing parameter nickname"),
+        };
+        if nickname != new_nickname && self.has_subaccount(&new_nickname) {
+            return reject("Subaccount already exists");
+        }
+        let subaccount = match self.subaccounts.iter_mut().find(|s| s.nickname == nickname) {
+            Some(subaccount) => subaccount,
+            None => return not_found("No such subaccount"),
+        };
+        subaccount.nickname = new_nickname.clone();
+
+        let (old, new) = (Some(nickname.to_string()), Some(new_nickname));
+        if let Some(balances) = self.balances.remove(&old) {
+            self.balances.insert(new.clone(), balances);
+        }
+        if let Some(positions) = self.positions.remove(&old) {
+            self.positions.insert(new.clone(), positions);
+        }
+        for (o, _) in self.orders.values_mut() {
+            if o == &old {
+                *o = new.clone();
+            }
+        }
+        for fill in self.fills.iter_mut() {
+            if fill.owner == old {
+                fill.owner = new.clone();
+            }
+        }
+        let histories = self.deposits.iter_mut().map(|(o, _)| o);
+        for o in histories.chain(self.funding_payments.iter_mut().map(|(o, _)| o)) {
+            if o == &old {
+                *o = new.clone();
+            }
+        }
+        Ok(Value::Null)
+    }
+
+    pub(crate) fn delete_subaccount(
+        &mut self,
+        owner: &Owner,
+        params: &Map<String, Value>,
+    ) -> Reply {
+        Self::main_account_only(owner)?;
+        let nickname = match str_param(params, "nickname") {
+            Some(nickname) => nickname,
+            None => return reject("Missing parameter nickname"),
+        };
+        if !self.has_subaccount(nickname) {
+            return not_found("No such subaccount");
+        }
+        let owner = Some(nickname.to_string());
+        let funded = self
+            .balances
+            .get(&owner)
+            .is_some_and(|balances| balances.values().any(|b| !b.is_zero()));
+        if funded {
+            return reject("Subaccount has non-zero balances");
+        }
+        self.subaccounts.retain(|s| s.nickname != nickname);
+        Ok(Value::Null)
+    }
+
+    pub(crate) fn get_subaccount_balances(&self, owner: &Owner, nickname: &str) -> Reply {
+        Self::main_account_only(owner)?;
+        if !self.has_subaccount(nickname) {
+            return not_found("No such subaccount");
+        }
+        let owner = Some(nickname.to_string());
+        let balances = self.get_balances(&owner)?;
+        // Subaccount balances are reported without their USD value
+        Ok(balances
+            .as_array()
+            .into_iter()
+            .flatten()
+            .map(|balance| {
+                let mut balance = balance.clone();
+                if let Some(balance) = balance.as_object_mut() {
+                    balance.remove("usdValue");
+                }
+                balance
+            })
+            .collect())
+    }
+
+    pub(crate) fn transfer_between_subaccounts(
+        &mut self,
+        owner: &Owner,
+        params: &Map<String, Value>,
+    ) -> Reply {
+        Self::main_account_only(owner)?;
+        let account = |key| match str_param(params, key) {
+            None | Some("main") => Ok(None),
+            Some(nickname) if self.has_subaccount(nickname) => Ok(Some(nickname.to_string())),
+            Some(_) => not_found("No such subaccount"),
+        };
+        let (source, destination) = (account("source")?, account("destination")?);
+        let coin = match str_param(params, "coin") {
+            Some(coin) => coin.to_string(),
+            None => return reject("Missing parameter coin"),
+        };
+        let size = match decimal_param(params, "size")? {
+            Some(size) if size > Decimal::zero() => size,
+            _ => return reject("Invalid size"),
+        };
+        if source == destination {
+            return reject("Cannot transfer to the same account");
+        }
+        let available = self
+            .balances
+            .get(&source)
+            .and_then(|balances| balances.get(&coin))
+            .copied()
+            .unwrap_or_default();
+        if available < size {
+            return reject("Not enough balances");
+        }
+
+        self.deposit(source, &coin, -size);
+        self.deposit(destination, &coin, size);
+        Ok(json!({
+            "id": self.next_id(),
+            "coin": coin,
+            "size": size,
+            "time": Utc::now(),
+            "notes": "",
+            "status": "complete",
+        }))
+    }
+}
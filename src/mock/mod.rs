@@ -0,0 +1,334 @@
+//! This module provides an in-process mock of the FTX REST API, which allows
+//! testing against `Rest` without network access or an FTX account.
+//!
+//! The mock checks `FTX-KEY`, `FTX-TS` and `FTX-SIGN` like the exchange does
+//! and keeps an in-memory order book per market. Orders are matched against
+//! each other and against liquidity seeded with `MockServer::set_orderbook`.
+//! Margin requirements, conditional orders and self-trade prevention are not
+//! simulated.
+
+mod exchange;
+#[cfg(test)]
+mod tests;
+
+pub use exchange::MockMarket;
+
+use crate::clock::local_millis;
+use crate::rest::Rest;
+use crate::signer::Signer;
+use chrono::{DateTime, TimeZone, Utc};
+use exchange::{Exchange, Owner, Rejection, Reply};
+use hyper::{
+    service::{make_service_fn, service_fn},
+    Body, Method, Request, Response, Server, StatusCode,
+};
+use percent_encoding::percent_decode_str;
+use rust_decimal::Decimal;
+use serde_json::{json, Map, Value};
+use std::{
+    convert::Infallible,
+    net::SocketAddr,
+    sync::{Arc, Mutex, MutexGuard},
+};
+use tokio::sync::oneshot;
+use url::Url;
+
+/// How far the `FTX-TS` header may deviate from the server time.
+const MAX_TIMESTAMP_DRIFT_MS: u64 = 30_000;
+
+/// An FTX REST API served on a local port until dropped.
+pub struct MockServer {
+    addr: SocketAddr,
+    exchange: Arc<Mutex<Exchange>>,
+    shutdown: Option<oneshot::Sender<()>>,
+}
+
+impl MockServer {
+    /// Starts serving on a free port of the loopback interface.
+    pub async fn start() -> Result<Self, hyper::Error> {
+        let exchange = Arc::new(Mutex::new(Exchange::default()));
+
+        let state = exchange.clone();
+        let make_service = make_service_fn(move |_| {
+            let state = state.clone();
+            async move { Ok::<_, Infallible>(service_fn(move |request| handle(state.clone(), request))) }
+        });
+
+        let server = Server::try_bind(&SocketAddr::from(([127, 0, 0, 1], 0)))?.serve(make_service);
+        let addr = server.local_addr();
+        let (shutdown, on_shutdown) = oneshot::channel();
+        tokio::spawn(server.with_graceful_shutdown(async {
+            on_shutdown.await.ok();
+        }));
+
+        Ok(Self {
+            addr,
+            exchange,
+            shutdown: Some(shutdown),
+        })
+    }
+
+    /// The URL to use in place of `Rest::ENDPOINT`.
+    pub fn endpoint(&self) -> String {
+        format!("http://{}/api", self.addr)
+    }
+
+    /// Returns a `Rest` client that talks to this server.
+    pub fn rest(
+        &self,
+        key: String,
+        secret: String,
+        subaccount: Option<String>,
+    ) -> crate::rest::Result<Rest> {
+        Rest::new_with_endpoint(&self.endpoint(), "FTX", key, secret, subaccount, None)
+    }
+
+    fn exchange(&self) -> MutexGuard<'_, Exchange> {
+        self.exchange.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Accepts requests signed with the given key pair.
+    pub fn add_api_key(&self, key: &str, secret: &str) {
+        self.exchange()
+            .api_keys
+            .insert(key.to_string(), Signer::new(secret.to_string()));
+    }
+
+    pub fn add_market(&self, market: MockMarket) {
+        self.exchange().add_market(market);
+    }
+
+    /// Replaces the liquidity resting in a market that does not belong to
+    /// any order, i.e. the rest of the market. Levels are `(price, size)`.
+    pub fn set_orderbook(
+        &self,
+        market: &str,
+        bids: &[(Decimal, Decimal)],
+        asks: &[(Decimal, Decimal)],
+    ) {
+        self.exchange().set_orderbook(market, bids, asks);
+    }
+
+    /// Credits a coin to the main account or to a subaccount.
+    pub fn deposit(&self, subaccount: Option<&str>, coin: &str, size: Decimal) {
+        self.exchange()
+            .record_deposit(subaccount.map(str::to_string), coin, size);
+    }
+
+    /// Records a funding payment of a future at `time`, debiting `payment`
+    /// USD. Negative payments are funding received.
+    pub fn add_funding_payment(
+        &self,
+        subaccount: Option<&str>,
+        future: &str,
+        payment: Decimal,
+        time: DateTime<Utc>,
+    ) {
+        self.exchange()
+            .add_funding_payment(subaccount.map(str::to_string), future, payment, time);
+    }
+
+    pub fn set_fees(&self, maker_fee: Decimal, taker_fee: Decimal) {
+        let mut exchange = self.exchange();
+        exchange.maker_fee = maker_fee;
+        exchange.taker_fee = taker_fee;
+    }
+
+    /// Simulates a server clock that is `offset` milliseconds ahead of the
+    /// local clock, or behind when negative.
+    pub fn set_clock_offset(&self, offset: i64) {
+        self.exchange().clock_offset = offset;
+    }
+}
+
+impl Drop for MockServer {
+    fn drop(&mut self) {
+        if let Some(shutdown) = self.shutdown.take() {
+            shutdown.send(()).ok();
+        }
+    }
+}
+
+async fn handle(
+    exchange: Arc<Mutex<Exchange>>,
+    request: Request<Body>,
+) -> Result<Response<Body>, Infallible> {
+    let (parts, body) = request.into_parts();
+    let body = hyper::body::to_bytes(body).await.unwrap_or_default();
+    let mut exchange = exchange.lock().unwrap_or_else(|e| e.into_inner());
+
+    let reply = route(&mut exchange, &parts, &body);
+    log::debug!("{} {}: {:?}", parts.method, parts.uri, reply);
+    let date = server_time(&exchange).format("%a, %d %b %Y %H:%M:%S GMT");
+
+    let (status, body) = match reply {
+        Ok(result) => (200, json!({ "success": true, "result": result })),
+        Err((status, error)) => (status, json!({ "success": false, "error": error })),
+    };
+    let mut response = Response::new(Body::from(body.to_string()));
+    *response.status_mut() = StatusCode::from_u16(status).unwrap_or(StatusCode::BAD_REQUEST);
+    response.headers_mut().insert(
+        hyper::header::CONTENT_TYPE,
+        hyper::header::HeaderValue::from_static("application/json"),
+    );
+    if let Ok(date) = hyper::header::HeaderValue::from_str(&date.to_string()) {
+        response.headers_mut().insert(hyper::header::DATE, date);
+    }
+
+    Ok(response)
+}
+
+fn server_time(exchange: &Exchange) -> chrono::DateTime<Utc> {
+    let now = local_millis() as i64 + exchange.clock_offset;
+    Utc.timestamp_millis_opt(now)
+        .single()
+        .unwrap_or_else(Utc::now)
+}
+
+fn header<'a>(parts: &'a hyper::http::request::Parts, name: &str) -> Option<&'a str> {
+    parts
+        .headers
+        .get(name)
+        .and_then(|value| value.to_str().ok())
+}
+
+fn not_logged_in<T>(reason: &str) -> Result<T, Rejection> {
+    Err((401, format!("Not logged in: {}", reason)))
+}
+
+/// Authenticates a request the way FTX does and returns the account it acts
+/// on. Requests without an API key are anonymous.
+fn authenticate(
+    exchange: &Exchange,
+    parts: &hyper::http::request::Parts,
+    body: &[u8],
+) -> Result<Option<Owner>, Rejection> {
+    let key = match header(parts, "FTX-KEY") {
+        Some(key) => key,
+        None => return Ok(None),
+    };
+    let signer = match exchange.api_keys.get(key) {
+        Some(signer) => signer,
+        None => return not_logged_in("Invalid API key"),
+    };
+    let timestamp = match header(parts, "FTX-TS").and_then(|ts| ts.parse::<u64>().ok()) {
+        Some(timestamp) => timestamp,
+        None => return not_logged_in("Invalid timestamp"),
+    };
+    let now = server_time(exchange).timestamp_millis() as u64;
+    if now.abs_diff(timestamp) > MAX_TIMESTAMP_DRIFT_MS {
+        return not_logged_in("Request timestamp expired");
+    }
+
+    let path = parts
+        .uri
+        .path_and_query()
+        .map(|path| path.as_str())
+        .unwrap_or("/");
+    let body = String::from_utf8_lossy(body);
+    let signature = header(parts, "FTX-SIGN").unwrap_or_default();
+    if !signer.verify_rest(timestamp, parts.method.as_str(), path, &body, signature) {
+        return not_logged_in("Invalid signature");
+    }
+
+    match header(parts, "FTX-SUBACCOUNT") {
+        None => Ok(Some(None)),
+        Some(nickname) if exchange.has_subaccount(nickname) => Ok(Some(Some(nickname.to_string()))),
+        Some(_) => not_logged_in("Invalid subaccount name"),
+    }
+}
+
+fn route(exchange: &mut Exchange, parts: &hyper::http::request::Parts, body: &[u8]) -> Reply {
+    let owner = authenticate(exchange, parts, body)?;
+
+    let url = match Url::parse(&format!("http://localhost{}", parts.uri)) {
+        Ok(url) => url,
+        Err(_) => return Err((400, "Invalid URL".to_string())),
+    };
+    let path = match url.path().strip_prefix("/api") {
+        Some(path) => path,
+        None => return Err((404, "Not Found".to_string())),
+    };
+    let segments: Vec<String> = path
+        .trim_start_matches('/')
+        .split('/')
+        .map(|segment| percent_decode_str(segment).decode_utf8_lossy().into_owned())
+        .collect();
+    let segments: Vec<&str> = segments.iter().map(String::as_str).collect();
+
+    let params: Map<String, Value> = if parts.method == Method::GET {
+        url.query_pairs()
+            .map(|(key, value)| (key.into_owned(), Value::String(value.into_owned())))
+            .collect()
+    } else if body.is_empty() {
+        Map::new()
+    } else {
+        match serde_json::from_slice(body) {
+            Ok(Value::Object(params)) => params,
+            _ => return Err((400, "Invalid JSON".to_string())),
+        }
+    };
+
+    // Public endpoints
+    match (&parts.method, segments.as_slice()) {
+        (&Method::GET, ["time"]) => return Ok(json!(server_time(exchange))),
+        (&Method::GET, ["markets"]) => return exchange.get_markets(),
+        // Market names such as BTC/USD span two segments
+        (&Method::GET, ["markets", market @ .., "orderbook"]) if !market.is_empty() => {
+            return exchange.get_orderbook(&market.join("/"), &params)
+        }
+        (&Method::GET, ["markets", market @ ..]) if !market.is_empty() => {
+            return exchange.get_market(&market.join("/"))
+        }
+        _ => {}
+    }
+
+    let owner = match owner {
+        Some(owner) => owner,
+        None => return Err((401, "Not logged in".to_string())),
+    };
+    let order_id = |id: &str| match id.parse() {
+        Ok(id) => Ok(id),
+        Err(_) => Err((404, "Order not found".to_string())),
+    };
+
+    match (&parts.method, segments.as_slice()) {
+        (&Method::GET, ["orders"]) => exchange.get_open_orders(&owner, &params),
+        (&Method::POST, ["orders"]) => exchange.place_order(&owner, &params),
+        (&Method::DELETE, ["orders"]) => exchange.cancel_all_orders(&owner, &params),
+        (&Method::GET, ["orders", "history"]) => exchange.get_order_history(&owner, &params),
+        (&Method::GET, ["orders", "by_client_id", client_id]) => {
+            exchange.get_order_by_client_id(&owner, client_id)
+        }
+        (&Method::DELETE, ["orders", "by_client_id", client_id]) => {
+            exchange.cancel_order_by_client_id(&owner, client_id)
+        }
+        (&Method::POST, ["orders", "by_client_id", client_id, "modify"]) => {
+            exchange.modify_order_by_client_id(&owner, client_id, &params)
+        }
+        (&Method::GET, ["orders", id]) => exchange.get_order(&owner, order_id(id)?),
+        (&Method::DELETE, ["orders", id]) => exchange.cancel_order(&owner, order_id(id)?),
+        (&Method::POST, ["orders", id, "modify"]) => {
+            exchange.modify_order(&owner, order_id(id)?, &params)
+        }
+        (&Method::GET, ["fills"]) => exchange.get_fills(&owner, &params),
+        (&Method::GET, ["account"]) => exchange.get_account(&owner),
+        (&Method::GET, ["positions"]) => exchange.get_positions(&owner),
+        (&Method::GET, ["funding_payments"]) => exchange.get_funding_payments(&owner, &params),
+        (&Method::GET, ["wallet", "balances"]) => exchange.get_balances(&owner),
+        (&Method::GET, ["wallet", "deposits"]) => exchange.get_deposits(&owner, &params),
+        (&Method::GET, ["subaccounts"]) => exchange.get_subaccounts(&owner),
+        (&Method::POST, ["subaccounts"]) => exchange.create_subaccount(&owner, &params),
+        (&Method::DELETE, ["subaccounts"]) => exchange.delete_subaccount(&owner, &params),
+        (&Method::POST, ["subaccounts", "update_name"]) => {
+            exchange.change_subaccount_name(&owner, &params)
+        }
+        (&Method::POST, ["subaccounts", "transfer"]) => {
+            exchange.transfer_between_subaccounts(&owner, &params)
+        }
+        (&Method::GET, ["subaccounts", nickname, "balances"]) => {
+            exchange.get_subaccount_balances(&owner, nickname)
+        }
+        _ => Err((404, "Not Found".to_string())),
+    }
+}
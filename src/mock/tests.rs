@@ -0,0 +1,746 @@
+use super::*;
+use crate::rest::{
+    DepositStatus, Error, FeeMismatch, FeeModel, FillInfo, FillType, GetMarkets, GetOrder,
+    Liquidity, Market, ModifyOrder, OrderRequest, OrderStatus, Side,
+};
+use rust_decimal_macros::dec;
+
+const KEY: &str = "mock-key";
+const SECRET: &str = "mock-secret";
+
+async fn init_server() -> MockServer {
+    let server = MockServer::start().await.expect("Server failed to start.");
+    server.add_api_key(KEY, SECRET);
+    server.add_market(MockMarket::spot("BTC", "USD", dec!(1), dec!(0.0001)));
+    server.add_market(MockMarket::future(
+        "ETH-PERP",
+        "ETH",
+        dec!(0.1),
+        dec!(0.001),
+    ));
+    server.set_orderbook(
+        "BTC/USD",
+        &[(dec!(39999), dec!(1)), (dec!(39998), dec!(2))],
+        &[(dec!(40001), dec!(1)), (dec!(40002), dec!(2))],
+    );
+    server.set_orderbook(
+        "ETH-PERP",
+        &[(dec!(2999.9), dec!(10))],
+        &[(dec!(3000.1), dec!(10))],
+    );
+    server.deposit(None, "USD", dec!(100000));
+    server
+}
+
+fn init_api(server: &MockServer) -> Rest {
+    server
+        .rest(KEY.to_string(), SECRET.to_string(), None)
+        .unwrap()
+}
+
+fn not_logged_in<T: std::fmt::Debug>(result: crate::rest::Result<T>, reason: &str) {
+    match result {
+        Err(Error::Api(error)) => assert_eq!(format!("Not logged in: {}", reason), error),
+        result => panic!("Expected authentication error, got {:?}.", result),
+    }
+}
+
+#[tokio::test]
+async fn authentication() {
+    let server = init_server().await;
+
+    // Credentials are checked whenever they are given, even for public endpoints
+    server
+        .rest(KEY.to_string(), "wrong".to_string(), None)
+        .unwrap()
+        .get_markets()
+        .await
+        .unwrap_err();
+    let markets = init_api(&server).get_markets().await.unwrap();
+    assert_eq!(2, markets.len());
+
+    not_logged_in(
+        server
+            .rest(KEY.to_string(), "wrong".to_string(), None)
+            .unwrap()
+            .get_account()
+            .await,
+        "Invalid signature",
+    );
+    not_logged_in(
+        server
+            .rest("unknown".to_string(), SECRET.to_string(), None)
+            .unwrap()
+            .get_account()
+            .await,
+        "Invalid API key",
+    );
+    not_logged_in(
+        server
+            .rest(
+                KEY.to_string(),
+                SECRET.to_string(),
+                Some("None".to_string()),
+            )
+            .unwrap()
+            .get_account()
+            .await,
+        "Invalid subaccount name",
+    );
+
+    // Query parameters are part of the signature
+    let orderbook = init_api(&server)
+        .get_orderbook("BTC/USD", Some(1))
+        .await
+        .unwrap();
+    assert_eq!(vec![(dec!(39999), dec!(1))], orderbook.bids);
+    assert_eq!(vec![(dec!(40001), dec!(1))], orderbook.asks);
+}
+
+#[tokio::test]
+async fn clock_skew() {
+    let server = init_server().await;
+    let api = init_api(&server);
+
+    server.set_clock_offset(60_000);
+    not_logged_in(api.get_account().await, "Request timestamp expired");
+
+    let skew = api.sync_time().await.unwrap();
+    assert!((skew - 60_000).abs() < 1_000, "skew: {}", skew);
+    assert_eq!(skew, api.clock_skew());
+    api.get_account().await.unwrap();
+
+    // The server clock being behind is compensated the same way
+    server.set_clock_offset(-60_000);
+    not_logged_in(api.get_account().await, "Request timestamp expired");
+    api.sync_time().await.unwrap();
+    api.get_account().await.unwrap();
+}
+
+#[tokio::test]
+async fn place_modify_cancel_orders() {
+    let server = init_server().await;
+    let api = init_api(&server);
+
+    let initial_order = api
+        .place_order(
+            OrderRequest::limit("BTC/USD", Side::Buy, dec!(39000), dec!(0.01))
+                .post_only(true)
+                .client_id("initial"),
+        )
+        .await
+        .unwrap();
+    assert_eq!(OrderStatus::New, initial_order.status);
+    assert_eq!(
+        OrderStatus::Open,
+        api.get_order(initial_order.id).await.unwrap().status
+    );
+    assert_eq!(1, api.get_open_orders("BTC/USD").await.unwrap().len());
+
+    // Modifying replaces the order
+    let modified_order = api
+        .modify_order_by_client_id("initial", Some(dec!(38000)), Some(dec!(0.02)))
+        .await
+        .unwrap();
+    assert_ne!(initial_order.id, modified_order.id);
+    assert_eq!(Some(dec!(38000)), modified_order.price);
+    assert_eq!(dec!(0.02), modified_order.size);
+    assert_eq!(
+        OrderStatus::Closed,
+        api.get_order(initial_order.id).await.unwrap().status
+    );
+
+    assert_eq!(
+        "Order queued for cancellation",
+        api.cancel_order(modified_order.id).await.unwrap()
+    );
+    api.cancel_order(modified_order.id).await.unwrap_err();
+    let cancelled_order = api.get_order(modified_order.id).await.unwrap();
+    assert_eq!(OrderStatus::Closed, cancelled_order.status);
+    assert_eq!(dec!(0), cancelled_order.filled_size);
+
+    // Post-only orders that would take liquidity are accepted, then rejected
+    let rejected_order = api
+        .place_order(
+            OrderRequest::limit("BTC/USD", Side::Buy, dec!(40001), dec!(0.01)).post_only(true),
+        )
+        .await
+        .unwrap();
+    assert_eq!(OrderStatus::New, rejected_order.status);
+    let rejected_order = api.get_order(rejected_order.id).await.unwrap();
+    assert_eq!(OrderStatus::Closed, rejected_order.status);
+    assert_eq!(dec!(0), rejected_order.filled_size);
+
+    // Sizes must be multiples of the size increment
+    api.place_order(OrderRequest::limit(
+        "BTC/USD",
+        Side::Buy,
+        dec!(39000),
+        dec!(0.00001),
+    ))
+    .await
+    .unwrap_err();
+
+    api.place_order(OrderRequest::limit(
+        "BTC/USD",
+        Side::Sell,
+        dec!(41000),
+        dec!(0.01),
+    ))
+    .await
+    .unwrap();
+    // Client IDs are encoded when they are part of the path
+    api.place_order(
+        OrderRequest::limit("BTC/USD", Side::Sell, dec!(42000), dec!(0.01)).client_id("a/b c?#"),
+    )
+    .await
+    .unwrap();
+    let order = api.get_order_by_client_id("a/b c?#").await.unwrap();
+    assert_eq!(Some("a/b c?#".to_string()), order.client_id);
+    api.cancel_order_by_client_id("a/b c?#").await.unwrap();
+
+    api.cancel_all_orders(Some("BTC/USD"), None, None, None)
+        .await
+        .unwrap();
+    assert!(api.get_open_orders("BTC/USD").await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn market_validation() {
+    let server = init_server().await;
+    let api = init_api(&server);
+    let market = api.get_market("BTC/USD").await.unwrap();
+
+    assert_eq!(dec!(39999), market.round_price(dec!(39999.7), Side::Buy));
+    assert_eq!(dec!(40000), market.round_price(dec!(39999.2), Side::Sell));
+    assert_eq!(dec!(0.0123), market.round_size(dec!(0.01239)));
+
+    // Rejected before being sent, whereas the exchange accepts it
+    let order = OrderRequest::limit("BTC/USD", Side::Buy, dec!(39000.5), dec!(0.01));
+    match api.place_order_for_market(order.clone(), &market).await {
+        Err(Error::InvalidOrder(_)) => {}
+        result => panic!("Invalid order error expected, got {:?}.", result),
+    }
+    let order = order.round_to(&market);
+    order.validate_for(&market).unwrap();
+    let order = api.place_order_for_market(order, &market).await.unwrap();
+    assert_eq!(Some(dec!(39000)), order.price);
+
+    let order = OrderRequest::limit("BTC/USD", Side::Sell, dec!(41000), dec!(0.00005));
+    assert!(order.validate_for(&market).is_err());
+    assert!(order.round_to(&market).validate_for(&market).is_err());
+
+    // Only orders that can rest on the book need the minimum provide size
+    let market = Market {
+        min_provide_size: dec!(0.01),
+        ..market
+    };
+    let order = OrderRequest::limit("BTC/USD", Side::Buy, dec!(39000), dec!(0.001));
+    assert!(order.validate_for(&market).is_err());
+    assert!(order.clone().post_only(true).validate_for(&market).is_err());
+    order.ioc(true).validate_for(&market).unwrap();
+    OrderRequest::market("BTC/USD", Side::Buy, dec!(0.001))
+        .validate_for(&market)
+        .unwrap();
+}
+
+#[tokio::test]
+async fn bulk_orders() {
+    let server = init_server().await;
+    let api = init_api(&server).with_concurrency(3);
+
+    // Results keep the order of the requests, failures included
+    let orders: Vec<_> = (0..6)
+        .map(|i| {
+            let size = if i == 4 { dec!(0.00001) } else { dec!(0.01) };
+            OrderRequest::limit("BTC/USD", Side::Buy, dec!(39000) - Decimal::from(i), size)
+        })
+        .collect();
+    let placed = api.place_orders(orders).await;
+    assert_eq!(6, placed.len());
+    assert!(placed[4].is_err());
+    for (i, order) in placed.iter().enumerate().filter(|(i, _)| *i != 4) {
+        let order = order.as_ref().unwrap();
+        assert_eq!(Some(dec!(39000) - Decimal::from(i)), order.price);
+    }
+
+    let mut ids: Vec<_> = placed
+        .into_iter()
+        .filter_map(|order| order.ok())
+        .map(|order| order.id)
+        .collect();
+    let modified = api
+        .modify_orders(
+            ids.iter()
+                .map(|&order_id| ModifyOrder {
+                    order_id,
+                    price: None,
+                    size: Some(dec!(0.02)),
+                    client_id: None,
+                })
+                .collect(),
+        )
+        .await;
+    assert!(modified
+        .iter()
+        .all(|order| order.as_ref().unwrap().size == dec!(0.02)));
+
+    ids = modified
+        .into_iter()
+        .map(|order| order.unwrap().id)
+        .collect();
+    ids.insert(1, 123_456_789);
+    let cancelled = api.cancel_orders(ids).await;
+    assert_eq!(6, cancelled.len());
+    assert!(cancelled[1].is_err());
+    assert_eq!(5, cancelled.iter().filter(|result| result.is_ok()).count());
+    assert!(api.get_open_orders("BTC/USD").await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn dry_run() {
+    let server = init_server().await;
+    let api = init_api(&server).with_dry_run(true);
+
+    // Reads go through
+    assert_eq!(2, api.get_markets().await.unwrap().len());
+
+    match api
+        .place_order(
+            OrderRequest::limit("BTC/USD", Side::Buy, dec!(39000), dec!(0.01)).client_id("dry"),
+        )
+        .await
+    {
+        Err(Error::DryRun { method, url, body }) => {
+            assert_eq!(Method::POST, method);
+            assert!(url.ends_with("/api/orders"));
+            assert!(body.contains(r#""clientId":"dry""#));
+        }
+        result => panic!("Dry run error expected, got {:?}.", result),
+    }
+    match api.delete_subaccount("Bot").await {
+        Err(Error::DryRun { method, .. }) => assert_eq!(Method::DELETE, method),
+        result => panic!("Dry run error expected, got {:?}.", result),
+    }
+
+    // Nothing reached the exchange
+    let api = api.with_dry_run(false);
+    assert!(api.get_open_orders("BTC/USD").await.unwrap().is_empty());
+    api.get_order_by_client_id("dry").await.unwrap_err();
+}
+
+#[tokio::test]
+async fn response_meta() {
+    let server = init_server().await;
+    let api = init_api(&server);
+
+    let response = api.send_with_meta(GetMarkets).await.unwrap();
+    assert_eq!(2, response.result.unwrap().len());
+    assert_eq!(200, response.status.as_u16());
+    assert_eq!("application/json", response.headers["content-type"]);
+    assert!(response.headers.contains_key("date"));
+    assert!(response.latency > std::time::Duration::ZERO);
+    assert!((chrono::Utc::now() - response.sent_at).num_seconds().abs() < 5);
+
+    // Errors reported by the API keep their metadata
+    let response = api.send_with_meta(GetOrder { order_id: 42 }).await.unwrap();
+    assert_eq!(404, response.status.as_u16());
+    match response.result {
+        Err(Error::Api(error)) => assert_eq!("Order not found", error),
+        result => panic!("Api error expected, got {:?}.", result),
+    }
+}
+
+#[tokio::test]
+async fn fills_and_balances() {
+    let server = init_server().await;
+    let api = init_api(&server);
+
+    // Takes all of the best ask and some of the next level
+    let order = api
+        .place_order(OrderRequest::market("BTC/USD", Side::Buy, dec!(1.5)))
+        .await
+        .unwrap();
+    let order = api.get_order(order.id).await.unwrap();
+    assert_eq!(OrderStatus::Closed, order.status);
+    assert_eq!(dec!(1.5), order.filled_size);
+    assert_eq!(
+        Some((dec!(40001) + dec!(0.5) * dec!(40002)) / dec!(1.5)),
+        order.avg_fill_price
+    );
+
+    let fills = api
+        .get_fills(Some("BTC/USD"), None, Some(order.id), None, None)
+        .await
+        .unwrap();
+    assert_eq!(2, fills.len());
+    assert!(fills.iter().all(|fill| fill.liquidity == Liquidity::Taker));
+    assert!(fills.iter().all(|fill| fill.fee_rate == dec!(0.0007)));
+
+    let balances = api.get_wallet_balances().await.unwrap();
+    let btc = balances.iter().find(|b| b.coin == "BTC").unwrap();
+    assert_eq!(dec!(1.5), btc.total);
+    let usd = balances.iter().find(|b| b.coin == "USD").unwrap();
+    let cost = dec!(40001) + dec!(0.5) * dec!(40002);
+    assert_eq!(dec!(100000) - cost * dec!(1.0007), usd.total);
+
+    // Resting orders are makers when taken
+    let bid = api
+        .place_order(
+            OrderRequest::limit("BTC/USD", Side::Buy, dec!(40000), dec!(0.5)).post_only(true),
+        )
+        .await
+        .unwrap();
+    api.place_order(OrderRequest::limit("BTC/USD", Side::Sell, dec!(39999), dec!(0.2)).ioc(true))
+        .await
+        .unwrap();
+    let bid = api.get_order(bid.id).await.unwrap();
+    assert_eq!(OrderStatus::Open, bid.status);
+    assert_eq!(dec!(0.2), bid.filled_size);
+    let maker_fill = &api
+        .get_fills(None, None, Some(bid.id), None, None)
+        .await
+        .unwrap()[0];
+    assert_eq!(Liquidity::Maker, maker_fill.liquidity);
+    assert_eq!(dec!(0.0002), maker_fill.fee_rate);
+}
+
+#[tokio::test]
+async fn fee_model() {
+    let server = init_server().await;
+    let api = init_api(&server);
+
+    let fees = api.get_fee_model().await.unwrap();
+    assert_eq!(FeeModel::new(dec!(0.0002), dec!(0.0007)), fees);
+
+    let bid = OrderRequest::limit("BTC/USD", Side::Buy, dec!(40000), dec!(0.5)).post_only(true);
+    assert_eq!(dec!(4), fees.estimate(&bid, dec!(40001)));
+    let buy = OrderRequest::market("BTC/USD", Side::Buy, dec!(1));
+    let estimate = fees.estimate(&buy, dec!(40001));
+    assert_eq!(dec!(28.0007), estimate);
+
+    let order = api.place_order(buy).await.unwrap();
+    let fills = api
+        .get_fills(None, None, Some(order.id), None, None)
+        .await
+        .unwrap();
+    assert_eq!(estimate, fills[0].fee);
+    assert!(fees.check_fills(&fills).is_empty());
+
+    // Fills after a change of the fee tier are flagged
+    server.set_fees(dec!(0.0001), dec!(0.0005));
+    let order = api
+        .place_order(OrderRequest::market("BTC/USD", Side::Sell, dec!(1)))
+        .await
+        .unwrap();
+    let fills = api
+        .get_fills(None, None, Some(order.id), None, None)
+        .await
+        .unwrap();
+    assert_eq!(
+        vec![FeeMismatch {
+            fill_id: fills[0].id,
+            liquidity: Liquidity::Taker,
+            expected_rate: dec!(0.0007),
+            fee_rate: dec!(0.0005),
+        }],
+        fees.check_fills(&fills)
+    );
+
+    // OTC fills, fee-free fills and rounding errors are not flagged
+    let otc = FillInfo {
+        r#type: FillType::Otc,
+        ..fills[0].clone()
+    };
+    let free = FillInfo {
+        fee_rate: dec!(0),
+        ..fills[0].clone()
+    };
+    let rounded = FillInfo {
+        fee_rate: dec!(0.00070000001),
+        ..fills[0].clone()
+    };
+    assert!(fees.check_fills(&[otc, free, rounded]).is_empty());
+    assert_eq!(
+        FeeModel::new(dec!(0.0001), dec!(0.0005)),
+        api.get_fee_model().await.unwrap()
+    );
+}
+
+#[tokio::test]
+async fn positions() {
+    let server = init_server().await;
+    let api = init_api(&server);
+
+    api.place_order(OrderRequest::market("ETH-PERP", Side::Sell, dec!(2)))
+        .await
+        .unwrap();
+
+    let account = api.get_account().await.unwrap();
+    assert_eq!(dec!(0.0007), account.taker_fee);
+    assert_eq!(1, account.positions.len());
+    let position = &api.get_positions().await.unwrap()[0];
+    assert_eq!("ETH-PERP", position.future);
+    assert_eq!(Side::Sell, position.side);
+    assert_eq!(dec!(-2), position.net_size);
+    assert_eq!(Some(dec!(2999.9)), position.entry_price);
+}
+
+#[tokio::test]
+async fn subaccounts() {
+    let server = init_server().await;
+    let api = init_api(&server);
+
+    api.create_subaccount("Bot").await.unwrap();
+    api.create_subaccount("Bot").await.unwrap_err();
+    api.change_subaccount_name("Bot", "Trader").await.unwrap();
+    assert_eq!("Trader", api.get_subaccounts().await.unwrap()[0].nickname);
+
+    let transfer = api
+        .transfer_between_subaccounts("USD", dec!(1000), "main", "Trader")
+        .await
+        .unwrap();
+    assert_eq!(dec!(1000), transfer.size);
+    api.transfer_between_subaccounts("USD", dec!(1000000), "main", "Trader")
+        .await
+        .unwrap_err();
+
+    let balances = api.get_subaccount_balances("Trader").await.unwrap();
+    assert_eq!(dec!(1000), balances[0].total);
+
+    // Requests on behalf of the subaccount only see its balances
+    let trader = api.with_subaccount("Trader");
+    assert_eq!(Some("Trader"), trader.subaccount());
+    assert_eq!(None, api.subaccount());
+    assert_eq!(
+        dec!(1000),
+        trader.get_wallet_balances().await.unwrap()[0].total
+    );
+    trader.get_subaccounts().await.unwrap_err();
+    assert_eq!(
+        dec!(99000),
+        trader
+            .without_subaccount()
+            .get_wallet_balances()
+            .await
+            .unwrap()[0]
+            .total
+    );
+
+    // Subaccounts with balances cannot be deleted
+    api.delete_subaccount("Trader").await.unwrap_err();
+    api.transfer_between_subaccounts("USD", dec!(1000), "Trader", "main")
+        .await
+        .unwrap();
+    api.delete_subaccount("Trader").await.unwrap();
+    assert!(api.get_subaccounts().await.unwrap().is_empty());
+}
+
+#[cfg(feature = "blocking")]
+#[tokio::test(flavor = "multi_thread")]
+async fn blocking_rest() {
+    use crate::rest::BlockingRest;
+
+    let server = init_server().await;
+    let endpoint = server.endpoint();
+
+    // The blocking client must not be created or used on the async runtime
+    tokio::task::spawn_blocking(move || {
+        let api = BlockingRest::new_with_endpoint(
+            &endpoint,
+            "FTX",
+            KEY.to_string(),
+            SECRET.to_string(),
+            None,
+            None,
+        )
+        .unwrap()
+        .with_concurrency(2);
+        assert_eq!(2, api.get_markets().unwrap().len());
+        let order = api
+            .place_order_with_meta(OrderRequest::limit(
+                "BTC/USD",
+                Side::Buy,
+                dec!(39000),
+                dec!(0.01),
+            ))
+            .unwrap()
+            .result
+            .unwrap();
+        assert_eq!(OrderStatus::Open, api.get_order(order.id).unwrap().status);
+        api.cancel_order(order.id).unwrap();
+        api.sync_time().unwrap();
+
+        let orders: Vec<_> = api
+            .place_orders(vec![
+                OrderRequest::limit("BTC/USD", Side::Buy, dec!(38000), dec!(0.01)),
+                OrderRequest::limit("FOO/BAR", Side::Buy, dec!(1), dec!(1)),
+            ])
+            .into_iter()
+            .map(|result| result.map(|order| order.id))
+            .collect();
+        assert!(orders[1].is_err());
+        let id = *orders[0].as_ref().unwrap();
+        let modified = api.modify_orders(vec![ModifyOrder {
+            order_id: id,
+            price: Some(dec!(37000)),
+            size: None,
+            client_id: None,
+        }]);
+        let id = modified[0].as_ref().unwrap().id;
+        assert!(api.cancel_orders(vec![id]).iter().all(Result::is_ok));
+
+        // Three orders take two batches of threads, and keep their order
+        let orders = api.place_orders(
+            [dec!(36000), dec!(36001), dec!(36002)]
+                .iter()
+                .map(|&price| OrderRequest::limit("BTC/USD", Side::Buy, price, dec!(0.01)))
+                .collect(),
+        );
+        assert_eq!(
+            vec![dec!(36000), dec!(36001), dec!(36002)],
+            orders
+                .into_iter()
+                .map(|order| order.unwrap().price.unwrap())
+                .collect::<Vec<_>>()
+        );
+
+        assert!(!api.is_dry_run());
+        let api = api.with_dry_run(true);
+        assert!(api.is_dry_run());
+        match api.place_order_with_meta(OrderRequest::market("BTC/USD", Side::Buy, dec!(0.01))) {
+            Err(Error::DryRun { .. }) => {}
+            result => panic!("Dry-run error expected, got {:?}.", result),
+        }
+    })
+    .await
+    .unwrap();
+}
+
+#[cfg(feature = "archive")]
+#[tokio::test]
+async fn archive_sync() {
+    use crate::archive::{Archive, SyncSummary};
+    use chrono::Duration;
+
+    let server = init_server().await;
+    let api = init_api(&server);
+    for hours in 1..=5 {
+        let time = Utc::now() - Duration::hours(hours);
+        server.add_funding_payment(None, "ETH-PERP", dec!(0.5), time);
+    }
+    api.place_order(OrderRequest::market("BTC/USD", Side::Buy, dec!(0.1)))
+        .await
+        .unwrap();
+    let resting = api
+        .place_order(OrderRequest::limit(
+            "BTC/USD",
+            Side::Buy,
+            dec!(39000),
+            dec!(0.1),
+        ))
+        .await
+        .unwrap();
+
+    // A page size of 4 makes the funding payments span several pages
+    let archive = Archive::in_memory().await.unwrap().with_page_size(4);
+    let summary = archive.sync(&api).await.unwrap();
+    assert_eq!(
+        SyncSummary {
+            fills: 1,
+            orders: 2,
+            deposits: 1,
+            funding_payments: 5,
+        },
+        summary
+    );
+    assert_eq!(SyncSummary::default(), archive.sync(&api).await.unwrap());
+
+    // Orders that were still open are updated by the next sync
+    api.cancel_order(resting.id).await.unwrap();
+    api.place_order(OrderRequest::market("BTC/USD", Side::Sell, dec!(0.1)))
+        .await
+        .unwrap();
+    // In a later second than the first deposit, so that syncing from it
+    // does not request the first one again
+    tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+    server.deposit(None, "BTC", dec!(1));
+    let summary = archive.sync(&api).await.unwrap();
+    assert_eq!(
+        (1, 1, 1, 0),
+        (
+            summary.fills,
+            summary.orders,
+            summary.deposits,
+            summary.funding_payments
+        )
+    );
+
+    let orders = archive.orders().await.unwrap();
+    assert_eq!(3, orders.len());
+    assert!(orders
+        .iter()
+        .all(|order| order.status == OrderStatus::Closed));
+    let mut fills = api.get_fills(None, None, None, None, None).await.unwrap();
+    fills.reverse();
+    assert_eq!(fills, archive.fills().await.unwrap());
+    let payments = archive.funding_payments().await.unwrap();
+    assert_eq!(5, payments.len());
+    assert!(payments.windows(2).all(|pair| pair[0].time <= pair[1].time));
+    assert_eq!(2, archive.deposits().await.unwrap().len());
+
+    // Deposits without a final status are updated by the next sync, even
+    // when newer deposits were stored
+    sqlx::query(
+        "UPDATE deposits SET status = 'pending', data = json_set(data, '$.status', 'pending') \
+         WHERE id = (SELECT id FROM deposits ORDER BY time LIMIT 1)",
+    )
+    .execute(archive.pool())
+    .await
+    .unwrap();
+    let status = || async {
+        archive
+            .deposits()
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|deposit| deposit.status)
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(
+        vec![
+            DepositStatus::Unknown("pending".to_string()),
+            DepositStatus::Confirmed
+        ],
+        status().await
+    );
+    assert_eq!(SyncSummary::default(), archive.sync(&api).await.unwrap());
+    assert_eq!(vec![DepositStatus::Confirmed; 2], status().await);
+}
+
+#[cfg(feature = "archive")]
+#[tokio::test]
+async fn archive_page_overflow() {
+    use crate::archive::Archive;
+    use chrono::{Duration, SubsecRound};
+
+    let server = init_server().await;
+    let api = init_api(&server);
+    let time = Utc::now().trunc_subsecs(0) - Duration::hours(1);
+    server.add_funding_payment(None, "ETH-PERP", dec!(0.5), time - Duration::hours(1));
+    for _ in 0..5 {
+        server.add_funding_payment(None, "ETH-PERP", dec!(0.5), time);
+    }
+    server.add_funding_payment(None, "ETH-PERP", dec!(0.5), time + Duration::hours(1));
+
+    // Records within the same second cannot be split across pages, so that
+    // second is requested with larger pages
+    for page_size in [1, 2, 3, 5] {
+        let archive = Archive::in_memory()
+            .await
+            .unwrap()
+            .with_page_size(page_size);
+        assert_eq!(7, archive.sync(&api).await.unwrap().funding_payments);
+        assert_eq!(7, archive.funding_payments().await.unwrap().len());
+    }
+}
@@ -0,0 +1,212 @@
+use super::*;
+use reqwest::blocking::{Client, ClientBuilder};
+
+/// A synchronous client for scripts and batch jobs that do not run an async
+/// runtime. It provides the same endpoint methods as `Rest` and shares its
+/// request building, signing and fixtures.
+///
+/// Like `reqwest::blocking`, it must not be used from within an async
+/// runtime, except in `tokio::task::spawn_blocking`.
+#[derive(Clone)]
+pub struct BlockingRest {
+    config: Config,
+    client: Client,
+}
+
+impl BlockingRest {
+    pub(crate) fn new_with_endpoint(
+        endpoint: &str,
+        header_prefix: &'static str,
+        key: String,
+        secret: String,
+        subaccount: Option<String>,
+        proxy: Option<String>,
+    ) -> Result<Self> {
+        let mut client_builder = ClientBuilder::new();
+        if let Some(proxy) = proxy {
+            client_builder =
+                client_builder.proxy(reqwest::Proxy::all(format!("socks5h://{}", proxy))?);
+        }
+
+        let client = client_builder.build()?;
+
+        Ok(Self {
+            config: Config::new(endpoint, header_prefix, key, secret, subaccount),
+            client,
+        })
+    }
+
+    pub fn new(
+        key: String,
+        secret: String,
+        subaccount: Option<String>,
+        proxy: Option<String>,
+    ) -> Result<Self> {
+        Self::new_with_endpoint(Rest::ENDPOINT, "FTX", key, secret, subaccount, proxy)
+    }
+
+    /// See `Rest::with_subaccount`.
+    pub fn with_subaccount(&self, nickname: impl Into<String>) -> Self {
+        let mut rest = self.clone();
+        rest.config.subaccount = Some(nickname.into());
+        rest
+    }
+
+    /// See `Rest::without_subaccount`.
+    pub fn without_subaccount(&self) -> Self {
+        let mut rest = self.clone();
+        rest.config.subaccount = None;
+        rest
+    }
+
+    pub fn subaccount(&self) -> Option<&str> {
+        self.config.subaccount.as_deref()
+    }
+
+    /// See `Rest::with_fixtures`.
+    pub fn with_fixtures(mut self, dir: impl Into<std::path::PathBuf>, mode: FixtureMode) -> Self {
+        self.config.fixtures = Some(Fixtures::new(dir, mode));
+        self
+    }
+
+    /// See `Rest::with_rate_limiter`.
+    pub fn with_rate_limiter(mut self, rate_limiter: RateLimiter) -> Self {
+        self.config.rate_limiter = Some(rate_limiter);
+        self
+    }
+
+    /// See `Rest::without_rate_limiter`.
+    pub fn without_rate_limiter(mut self) -> Self {
+        self.config.rate_limiter = None;
+        self
+    }
+
+    /// See `Rest::with_concurrency`. Bulk operations send their requests
+    /// from that many threads at once.
+    pub fn with_concurrency(mut self, concurrency: usize) -> Self {
+        self.config.concurrency = concurrency.max(1);
+        self
+    }
+
+    /// See `Rest::with_dry_run`.
+    pub fn with_dry_run(mut self, dry_run: bool) -> Self {
+        self.config.dry_run = dry_run;
+        self
+    }
+
+    pub fn is_dry_run(&self) -> bool {
+        self.config.dry_run
+    }
+
+    /// See `Rest::with_clock`.
+    pub fn with_clock(mut self, clock: Clock) -> Self {
+        self.config.clock = clock;
+        self
+    }
+
+    pub fn clock(&self) -> &Clock {
+        &self.config.clock
+    }
+
+    pub fn clock_skew(&self) -> i64 {
+        self.config.clock.offset()
+    }
+
+    /// See `Rest::sync_time`.
+    pub fn sync_time(&self) -> Result<i64> {
+        let sent = local_millis();
+        let response = self
+            .client
+            .get(format!("{}/time", self.config.endpoint))
+            .send()?;
+        let date = response.headers().get(reqwest::header::DATE).cloned();
+        let body = response.bytes()?;
+        let received = local_millis();
+
+        self.config
+            .update_clock(sent, received, date.as_ref(), &body)
+    }
+
+    /// See `Rest::send`.
+    pub fn send<R: Request>(&self, request: R) -> Result<R::Response> {
+        self.send_with_meta(request)?.result
+    }
+
+    /// See `Rest::send_with_meta`.
+    pub fn send_with_meta<R: Request>(&self, request: R) -> Result<Response<R::Response>> {
+        let prepared = self.config.prepare_request(&request)?;
+        self.config.check_dry_run(&prepared)?;
+        if let Some(replayed) = self.config.replay(&prepared)? {
+            return Ok(replayed);
+        }
+        if let Some(rate_limiter) = &self.config.rate_limiter {
+            rate_limiter.acquire_blocking();
+        }
+
+        let start = Instant::now();
+        let response = self
+            .client
+            .request(prepared.method.clone(), prepared.url.clone())
+            .headers(prepared.headers.clone())
+            .body(prepared.body.clone())
+            .send()?;
+        let status = response.status();
+        let headers = response.headers().clone();
+        let body = response.bytes()?;
+        let latency = start.elapsed();
+
+        self.config
+            .finish(&prepared, status, headers, latency, &body)
+    }
+}
+
+impl BlockingRest {
+    endpoints!(,);
+
+    /// See `Rest::place_order_with_meta`.
+    pub fn place_order_with_meta(&self, order: OrderRequest) -> Result<Response<OrderInfo>> {
+        order.validate()?;
+        self.send_with_meta(order)
+    }
+
+    /// Places orders concurrently, see `with_concurrency`. The results are
+    /// in the order of `orders`, each either the placed order or the reason
+    /// it was not placed.
+    pub fn place_orders(&self, orders: Vec<OrderRequest>) -> Vec<Result<OrderInfo>> {
+        self.concurrently(orders, |order| self.place_order(order))
+    }
+
+    /// Cancels orders concurrently, with results in the order of `order_ids`.
+    pub fn cancel_orders(&self, order_ids: Vec<Id>) -> Vec<Result<String>> {
+        self.concurrently(order_ids, |order_id| self.cancel_order(order_id))
+    }
+
+    /// Modifies orders concurrently, with results in the order of
+    /// `modifications`.
+    pub fn modify_orders(&self, modifications: Vec<ModifyOrder>) -> Vec<Result<OrderInfo>> {
+        self.concurrently(modifications, |modification| self.send(modification))
+    }
+
+    /// Calls `f` with every item, from up to `concurrency` threads at a
+    /// time, and returns the results in the order of `items`.
+    fn concurrently<T: Send, R: Send>(&self, items: Vec<T>, f: impl Fn(T) -> R + Sync) -> Vec<R> {
+        let f = &f;
+        let mut items = items.into_iter().peekable();
+        let mut results = Vec::new();
+        while items.peek().is_some() {
+            let batch: Vec<T> = items.by_ref().take(self.config.concurrency).collect();
+            std::thread::scope(|scope| {
+                let threads: Vec<_> = batch
+                    .into_iter()
+                    .map(|item| scope.spawn(move || f(item)))
+                    .collect();
+                results.extend(threads.into_iter().map(|thread| {
+                    thread
+                        .join()
+                        .unwrap_or_else(|panic| std::panic::resume_unwind(panic))
+                }));
+            });
+        }
+        results
+    }
+}
@@ -0,0 +1,356 @@
+/// Defines the endpoint methods shared by `Rest` and `BlockingRest`. Both
+/// clients provide a `send` method, which is awaited when invoked with
+/// `async, .await` and called directly when invoked with `,`.
+macro_rules! endpoints {
+    ($($async:ident)?, $($await:tt)*) => {
+        pub $($async)? fn get_subaccounts(&self) -> Result<Subaccounts> {
+            self.send(GetSubaccounts)$($await)*
+        }
+
+        pub $($async)? fn create_subaccount(&self, nickname: &str) -> Result<Create> {
+            self.send(CreateSubaccount {
+                nickname: nickname.to_string(),
+            })
+            $($await)*
+        }
+
+        pub $($async)? fn change_subaccount_name(
+            &self,
+            nickname: &str,
+            new_nickname: &str,
+        ) -> Result<ChangeName> {
+            self.send(ChangeSubaccountName {
+                nickname: nickname.to_string(),
+                new_nickname: new_nickname.to_string(),
+            })
+            $($await)*
+        }
+
+        pub $($async)? fn delete_subaccount(&self, nickname: &str) -> Result<Delete> {
+            self.send(DeleteSubaccount {
+                nickname: nickname.to_string(),
+            })
+            $($await)*
+        }
+
+        pub $($async)? fn get_subaccount_balances(&self, nickname: &str) -> Result<Balances> {
+            self.send(GetSubaccountBalances {
+                nickname: nickname.to_string(),
+            })
+            $($await)*
+        }
+
+        pub $($async)? fn transfer_between_subaccounts(
+            &self,
+            coin: &str,
+            size: Decimal,
+            source: &str,
+            destination: &str,
+        ) -> Result<Transfer> {
+            self.send(TransferBetweenSubaccounts {
+                coin: coin.to_string(),
+                size,
+                source: source.to_string(),
+                destination: destination.to_string(),
+            })
+            $($await)*
+        }
+
+        pub $($async)? fn get_markets(&self) -> Result<Markets> {
+            self.send(GetMarkets)$($await)*
+        }
+
+        pub $($async)? fn get_market(&self, market_name: &str) -> Result<Market> {
+            self.send(GetMarket {
+                market_name: market_name.to_string(),
+            })
+            $($await)*
+        }
+
+        pub $($async)? fn get_orderbook(&self, market_name: &str, depth: Option<u32>) -> Result<Orderbook> {
+            self.send(GetOrderbook {
+                market_name: market_name.to_string(),
+                depth,
+            })
+            $($await)*
+        }
+
+        pub $($async)? fn get_trades(
+            &self,
+            market_name: &str,
+            limit: Option<u32>,
+            start_time: Option<DateTime<Utc>>,
+            end_time: Option<DateTime<Utc>>,
+        ) -> Result<Trades> {
+            self.send(GetTrades {
+                market_name: market_name.to_string(),
+                limit,
+                start_time,
+                end_time,
+            })
+            $($await)*
+        }
+
+        pub $($async)? fn get_historical_prices(
+            &self,
+            market_name: &str,
+            resolution: Resolution,
+            limit: Option<u32>,
+            start_time: Option<DateTime<Utc>>,
+            end_time: Option<DateTime<Utc>>,
+        ) -> Result<Prices> {
+            self.send(GetHistoricalPrices {
+                market_name: market_name.to_string(),
+                resolution,
+                limit,
+                start_time,
+                end_time,
+            })
+            $($await)*
+        }
+
+        /// The candles from `start_time` to `end_time`, oldest first. Long
+        /// ranges are requested in chunks of `GetHistoricalPrices::MAX_CANDLES`.
+        pub $($async)? fn get_historical_prices_range(
+            &self,
+            market_name: &str,
+            resolution: Resolution,
+            start_time: DateTime<Utc>,
+            end_time: DateTime<Utc>,
+        ) -> Result<Prices> {
+            let mut prices = Prices::new();
+            let requests =
+                GetHistoricalPrices::chunks(market_name, resolution, start_time, end_time);
+            for request in requests {
+                prices.extend(self.send(request)$($await)*?);
+            }
+            prices.sort_by_key(|price| price.start_time);
+            prices.dedup_by_key(|price| price.start_time);
+            Ok(prices)
+        }
+
+        pub $($async)? fn get_futures(&self) -> Result<Futures> {
+            self.send(GetFutures)$($await)*
+        }
+
+        pub $($async)? fn get_future(&self, future_name: &str) -> Result<Future> {
+            self.send(GetFuture {
+                future_name: future_name.to_string(),
+            })
+            $($await)*
+        }
+
+        pub $($async)? fn get_account(&self) -> Result<Account> {
+            self.send(GetAccount)$($await)*
+        }
+
+        /// The fee rates of the account, see `FeeModel`.
+        pub $($async)? fn get_fee_model(&self) -> Result<FeeModel> {
+            let account = self.get_account()$($await)*?;
+            Ok(FeeModel::from(&account))
+        }
+
+        pub $($async)? fn get_positions(&self) -> Result<Positions> {
+            self.send(GetPositions)$($await)*
+        }
+
+        pub $($async)? fn get_coins(&self) -> Result<Vec<CoinInfo>> {
+            self.send(GetCoins)$($await)*
+        }
+
+        pub $($async)? fn get_wallet_deposit_address(
+            &self,
+            coin: &str,
+            method: Option<&str>,
+        ) -> Result<WalletDepositAddress> {
+            self.send(GetWalletDepositAddress {
+                coin: coin.to_string(),
+                method: method.map(str::to_string),
+            })
+            $($await)*
+        }
+
+        pub $($async)? fn get_wallet_balances(&self) -> Result<Vec<WalletBalance>> {
+            self.send(GetWalletBalances)$($await)*
+        }
+
+        pub $($async)? fn get_wallet_deposits(
+            &self,
+            limit: Option<usize>,
+            start_time: Option<DateTime<Utc>>,
+            end_time: Option<DateTime<Utc>>,
+        ) -> Result<Vec<WalletDeposit>> {
+            self.send(GetWalletDeposits {
+                limit,
+                start_time,
+                end_time,
+            })
+            $($await)*
+        }
+
+        pub $($async)? fn get_open_orders(&self, market: &str) -> Result<Vec<OrderInfo>> {
+            self.send(GetOpenOrders {
+                market: Some(market.to_string()),
+            })
+            $($await)*
+        }
+
+        pub $($async)? fn get_fills(
+            &self,
+            market: Option<&str>,
+            limit: Option<usize>,
+            order_id: Option<Id>,
+            start_time: Option<DateTime<Utc>>,
+            end_time: Option<DateTime<Utc>>,
+        ) -> Result<Vec<FillInfo>> {
+            self.send(GetFills {
+                market: market.map(str::to_string),
+                limit,
+                order_id,
+                start_time,
+                end_time,
+            })
+            $($await)*
+        }
+
+        pub $($async)? fn get_order_history(
+            &self,
+            market: Option<&str>,
+            limit: Option<usize>,
+            start_time: Option<DateTime<Utc>>,
+            end_time: Option<DateTime<Utc>>,
+        ) -> Result<Vec<OrderInfo>> {
+            self.send(GetOrderHistory {
+                market: market.map(str::to_string),
+                limit,
+                start_time,
+                end_time,
+            })
+            $($await)*
+        }
+
+        pub $($async)? fn get_conditional_order_history(
+            &self,
+            market: Option<&str>,
+            limit: Option<usize>,
+            start_time: Option<DateTime<Utc>>,
+            end_time: Option<DateTime<Utc>>,
+        ) -> Result<Vec<ConditionalOrderInfo>> {
+            self.send(GetConditionalOrderHistory {
+                market: market.map(str::to_string),
+                limit,
+                start_time,
+                end_time,
+            })
+            $($await)*
+        }
+
+        /// Validates and places an order, see `OrderRequest`.
+        pub $($async)? fn place_order(&self, order: OrderRequest) -> Result<OrderInfo> {
+            order.validate()?;
+            self.send(order)$($await)*
+        }
+
+        /// Like `place_order`, but first checks the order against the increments
+        /// and minimum size of its market, see `OrderRequest::validate_for`.
+        pub $($async)? fn place_order_for_market(
+            &self,
+            order: OrderRequest,
+            market: &Market,
+        ) -> Result<OrderInfo> {
+            order.validate_for(market)?;
+            self.send(order)$($await)*
+        }
+
+        pub $($async)? fn place_conditional_order(
+            &self,
+            info: PlaceConditionalOrder,
+        ) -> Result<ConditionalOrderInfo> {
+            self.send(info)$($await)*
+        }
+
+        pub $($async)? fn modify_order(
+            &self,
+            order_id: Id,
+            price: Option<Decimal>,
+            size: Option<Decimal>,
+            client_id: Option<&str>,
+        ) -> Result<OrderInfo> {
+            self.send(ModifyOrder {
+                order_id,
+                price,
+                size,
+                client_id: client_id.map(str::to_string),
+            })
+            $($await)*
+        }
+
+        pub $($async)? fn modify_order_by_client_id(
+            &self,
+            client_id: &str,
+            price: Option<Decimal>,
+            size: Option<Decimal>,
+        ) -> Result<OrderInfo> {
+            self.send(ModifyOrderByClientId {
+                client_id: client_id.to_string(),
+                price,
+                size,
+            })
+            $($await)*
+        }
+
+        pub $($async)? fn get_order(&self, order_id: Id) -> Result<OrderInfo> {
+            self.send(GetOrder { order_id })$($await)*
+        }
+
+        pub $($async)? fn get_order_by_client_id(&self, client_id: &str) -> Result<OrderInfo> {
+            self.send(GetOrderByClientId {
+                client_id: client_id.to_string(),
+            })
+            $($await)*
+        }
+
+        pub $($async)? fn cancel_all_orders(
+            &self,
+            market: Option<&str>,
+            side: Option<Side>,
+            conditional_orders_only: Option<bool>,
+            limit_orders_only: Option<bool>,
+        ) -> Result<String> {
+            self.send(CancelAllOrders {
+                market: market.map(str::to_string),
+                side,
+                conditional_orders_only: conditional_orders_only.unwrap_or(false),
+                limit_orders_only: limit_orders_only.unwrap_or(false),
+            })
+            $($await)*
+        }
+
+        pub $($async)? fn cancel_order(&self, order_id: Id) -> Result<String> {
+            self.send(CancelOrder { order_id })$($await)*
+        }
+
+        pub $($async)? fn cancel_order_by_client_id(&self, client_id: &str) -> Result<String> {
+            self.send(CancelOrderByClientId {
+                client_id: client_id.to_string(),
+            })
+            $($await)*
+        }
+
+        pub $($async)? fn get_funding_payments(
+            &self,
+            future: Option<&str>,
+            limit: Option<u32>,
+            start_time: Option<DateTime<Utc>>,
+            end_time: Option<DateTime<Utc>>,
+        ) -> Result<Vec<FundingPayment>> {
+            self.send(GetFundingPayments {
+                future: future.map(str::to_string),
+                limit,
+                start_time,
+                end_time,
+            })
+            $($await)*
+        }
+    };
+}
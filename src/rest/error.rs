@@ -1,3 +1,9 @@
+use reqwest::{
+    header::{InvalidHeaderName, InvalidHeaderValue},
+    Method, StatusCode,
+};
+use serde_json::Value;
+use std::path::PathBuf;
 use thiserror::Error;
 
 pub type Result<T> = std::result::Result<T, Error>;
@@ -10,9 +16,58 @@ pub enum Error {
     #[error("placing limit order requires price")]
     PlacingLimitOrderRequiresPrice,
 
+    #[error("invalid order: {0}")]
+    InvalidOrder(&'static str),
+
+    #[error("invalid market symbol {0:?}")]
+    InvalidSymbol(String),
+
+    #[error("invalid candle resolution of {0} seconds")]
+    InvalidResolution(i64),
+
+    #[error("invalid request parameters: {0}")]
+    InvalidParams(Value),
+
+    #[error("unexpected response (HTTP {status}): {source}, body: {body}")]
+    UnexpectedResponse {
+        status: StatusCode,
+        /// Raw response body, truncated to a reasonable length.
+        body: String,
+        source: serde_json::Error,
+    },
+
+    #[error("dry run: {method} {url} was not sent")]
+    DryRun {
+        method: Method,
+        url: String,
+        /// JSON body of the request that would have been sent.
+        body: String,
+    },
+
+    #[error("server time missing from response")]
+    MissingServerTime,
+
+    #[error("no recorded response in fixture {0:?}")]
+    MissingFixture(PathBuf),
+
+    #[error("fixture {0:?} could not be read")]
+    InvalidFixture(PathBuf),
+
+    #[error(transparent)]
+    InvalidHeaderName(#[from] InvalidHeaderName),
+
+    #[error(transparent)]
+    InvalidHeaderValue(#[from] InvalidHeaderValue),
+
+    #[error(transparent)]
+    Url(#[from] url::ParseError),
+
     #[error(transparent)]
     Reqwest(#[from] reqwest::Error),
 
     #[error(transparent)]
     Json(#[from] serde_json::Error),
+
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
 }
@@ -0,0 +1,117 @@
+use super::{Account, FillInfo, FillType, Id, Liquidity, OrderRequest, OrderType};
+use rust_decimal::prelude::*;
+use rust_decimal_macros::dec;
+use serde::{Deserialize, Serialize};
+
+/// The maker and taker fee rates of an account, loaded with
+/// `Rest::get_fee_model` or from an `Account`.
+///
+/// It estimates the fees of orders before they are placed, and checks the
+/// fee rates of fills, which no longer match after the fee tier of the
+/// account changed.
+#[derive(Copy, Clone, Debug, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct FeeModel {
+    pub maker_fee: Decimal,
+    pub taker_fee: Decimal,
+}
+
+/// A fill that was charged another fee rate than the model expects.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FeeMismatch {
+    pub fill_id: Id,
+    pub liquidity: Liquidity,
+    pub expected_rate: Decimal,
+    pub fee_rate: Decimal,
+}
+
+impl FeeModel {
+    /// Fee rates closer than this to the expected rate are not flagged, as
+    /// FTX may report them with rounding errors.
+    pub const TOLERANCE: Decimal = dec!(0.0000001);
+
+    pub fn new(maker_fee: Decimal, taker_fee: Decimal) -> Self {
+        Self {
+            maker_fee,
+            taker_fee,
+        }
+    }
+
+    pub fn rate(&self, liquidity: Liquidity) -> Decimal {
+        match liquidity {
+            Liquidity::Maker => self.maker_fee,
+            Liquidity::Taker => self.taker_fee,
+        }
+    }
+
+    /// The liquidity `order` is expected to have. Only post-only orders are
+    /// sure to provide liquidity, so all other orders are assumed to take.
+    pub fn liquidity(order: &OrderRequest) -> Liquidity {
+        if order.post_only {
+            Liquidity::Maker
+        } else {
+            Liquidity::Taker
+        }
+    }
+
+    /// The fee of trading `size` at `price`.
+    pub fn fee(&self, liquidity: Liquidity, price: Decimal, size: Decimal) -> Decimal {
+        price * size * self.rate(liquidity)
+    }
+
+    /// The expected fee of `order` if it fills completely, in the quote
+    /// currency. Limit orders are valued at their price, and market orders
+    /// at `market_price`.
+    pub fn estimate(&self, order: &OrderRequest, market_price: Decimal) -> Decimal {
+        let price = match (order.r#type, order.price) {
+            (OrderType::Limit, Some(price)) => price,
+            _ => market_price,
+        };
+        self.fee(Self::liquidity(order), price, order.size)
+    }
+
+    /// Compares the fee rate of `fill` to the rate of its liquidity, and
+    /// logs a warning if they differ by more than `TOLERANCE`. OTC and
+    /// fee-free fills, e.g. from promotions, are not charged the tier rates
+    /// and are never flagged.
+    pub fn check_fill(&self, fill: &FillInfo) -> Option<FeeMismatch> {
+        if fill.r#type == FillType::Otc || fill.fee_rate.is_zero() {
+            return None;
+        }
+        let expected_rate = self.rate(fill.liquidity);
+        if (fill.fee_rate - expected_rate).abs() <= Self::TOLERANCE {
+            return None;
+        }
+        log::warn!(
+            "Fill {} was charged a {:?} fee rate of {} instead of {}, the fee tier may have \
+             changed",
+            fill.id,
+            fill.liquidity,
+            fill.fee_rate,
+            expected_rate
+        );
+        Some(FeeMismatch {
+            fill_id: fill.id,
+            liquidity: fill.liquidity,
+            expected_rate,
+            fee_rate: fill.fee_rate,
+        })
+    }
+
+    /// The fills whose fee rate differs from the model, see `check_fill`.
+    pub fn check_fills<'a>(
+        &self,
+        fills: impl IntoIterator<Item = &'a FillInfo>,
+    ) -> Vec<FeeMismatch> {
+        fills
+            .into_iter()
+            .filter_map(|fill| self.check_fill(fill))
+            .collect()
+    }
+}
+
+impl From<&Account> for FeeModel {
+    fn from(account: &Account) -> Self {
+        Self::new(account.maker_fee, account.taker_fee)
+    }
+}
@@ -0,0 +1,127 @@
+use super::{Error, Result};
+use reqwest::{Method, StatusCode};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::{fs, path::PathBuf};
+
+/// Whether responses are written to or served from the fixture directory.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum FixtureMode {
+    /// Send requests to the exchange and save every response.
+    Record,
+    /// Serve previously recorded responses without any network access.
+    Replay,
+}
+
+/// A directory of recorded request/response pairs.
+#[derive(Clone, Debug)]
+pub struct Fixtures {
+    dir: PathBuf,
+    mode: FixtureMode,
+}
+
+/// A single recorded request/response pair, stored as one JSON file.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct Fixture {
+    method: String,
+    path: String,
+    params: Option<Map<String, Value>>,
+    body: String,
+    status: u16,
+    response: String,
+}
+
+impl Fixtures {
+    pub fn new(dir: impl Into<PathBuf>, mode: FixtureMode) -> Self {
+        Self {
+            dir: dir.into(),
+            mode,
+        }
+    }
+
+    pub fn mode(&self) -> FixtureMode {
+        self.mode
+    }
+
+    /// Returns the file a request is recorded to. The name is derived from
+    /// everything that identifies the request, but not from its timestamp or
+    /// signature, so that the same request always maps to the same file.
+    pub fn path(
+        &self,
+        method: &Method,
+        path: &str,
+        params: &Option<Map<String, Value>>,
+        body: &str,
+    ) -> PathBuf {
+        let params = params
+            .as_ref()
+            .map(|params| Value::Object(params.clone()).to_string())
+            .unwrap_or_default();
+
+        let mut hasher = crc32fast::Hasher::new();
+        for part in [method.as_str(), path, &params, body] {
+            hasher.update(part.as_bytes());
+            hasher.update(&[0]);
+        }
+
+        let name: String = format!("{}{}", method, path)
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+            .take(100)
+            .collect();
+
+        self.dir
+            .join(format!("{}-{:08x}.json", name, hasher.finalize()))
+    }
+
+    /// Loads the recorded status and response body of a request.
+    pub(crate) fn load(
+        &self,
+        method: &Method,
+        path: &str,
+        params: &Option<Map<String, Value>>,
+        body: &str,
+    ) -> Result<(StatusCode, Vec<u8>)> {
+        let file = self.path(method, path, params, body);
+        let contents = match fs::read(&file) {
+            Ok(contents) => contents,
+            Err(_) => return Err(Error::MissingFixture(file)),
+        };
+        let fixture: Fixture = match serde_json::from_slice(&contents) {
+            Ok(fixture) => fixture,
+            Err(_) => return Err(Error::InvalidFixture(file)),
+        };
+        let status = match StatusCode::from_u16(fixture.status) {
+            Ok(status) => status,
+            Err(_) => return Err(Error::InvalidFixture(file)),
+        };
+
+        Ok((status, fixture.response.into_bytes()))
+    }
+
+    /// Records the status and response body of a request.
+    pub(crate) fn save(
+        &self,
+        method: &Method,
+        path: &str,
+        params: &Option<Map<String, Value>>,
+        body: &str,
+        status: StatusCode,
+        response: &[u8],
+    ) -> Result<()> {
+        let file = self.path(method, path, params, body);
+        let fixture = Fixture {
+            method: method.to_string(),
+            path: path.to_string(),
+            params: params.clone(),
+            body: body.to_string(),
+            status: status.as_u16(),
+            response: String::from_utf8_lossy(response).into_owned(),
+        };
+
+        fs::create_dir_all(&self.dir)?;
+        fs::write(file, serde_json::to_vec_pretty(&fixture)?)?;
+
+        Ok(())
+    }
+}
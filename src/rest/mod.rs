@@ -1,733 +1,584 @@
 //! This module is used to interact with the REST API.
 
+#[macro_use]
+mod endpoints;
+#[cfg(feature = "blocking")]
+mod blocking;
 mod error;
+mod fee;
+mod fixture;
 mod model;
+mod rate_limit;
+mod request;
+mod symbol;
 #[cfg(test)]
 pub(crate) mod tests;
 
+#[cfg(feature = "blocking")]
+pub use blocking::*;
 pub use error::*;
+pub use fee::*;
+pub use fixture::*;
 pub use model::*;
-
-use chrono::{DateTime, Utc};
-use hmac_sha256::HMAC;
+pub use rate_limit::*;
+pub use request::*;
+pub use symbol::*;
+
+use crate::clock::{local_millis, Clock};
+use crate::signer::Signer;
+use chrono::{DateTime, TimeZone, Utc};
+use futures::stream::{self, StreamExt};
 use reqwest::{
     header::{HeaderMap, HeaderName, HeaderValue},
-    Client, ClientBuilder, Method,
+    Client, ClientBuilder, StatusCode, Url,
 };
 use rust_decimal::prelude::*;
 use serde::de::DeserializeOwned;
-use serde_json::{from_reader, json, to_string, Map, Value};
-use std::{
-    io::Read,
-    time::{SystemTime, UNIX_EPOCH},
-};
-
-use serde::{Deserialize, Serialize};
+use serde_json::{from_slice, Map, Value};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
+/// A REST API client. Clones share the connection pool and credentials,
+/// so handles for other subaccounts are cheap, see `with_subaccount`.
+#[derive(Clone)]
 pub struct Rest {
-    secret: String,
+    config: Config,
     client: Client,
+}
+
+/// The credentials, clock and settings of a client, which build and sign
+/// requests independently of the HTTP client that sends them.
+#[derive(Clone)]
+struct Config {
+    key: String,
+    signer: Arc<Signer>,
+    clock: Clock,
     subaccount: Option<String>,
-    endpoint: &'static str,
+    endpoint: String,
     header_prefix: &'static str,
+    fixtures: Option<Fixtures>,
+    rate_limiter: Option<RateLimiter>,
+    concurrency: usize,
+    dry_run: bool,
+}
+
+/// A response together with its metadata, see `Rest::send_with_meta`.
+#[derive(Debug)]
+pub struct Response<T> {
+    /// The parsed result, or the error reported by the API.
+    pub result: Result<T>,
+    pub status: StatusCode,
+    pub headers: HeaderMap,
+    /// The time the request was signed with, i.e. its `FTX-TS` header.
+    pub sent_at: DateTime<Utc>,
+    /// Time from sending the request until the whole response was received,
+    /// not including waiting for the rate limiter.
+    pub latency: Duration,
 }
 
-#[derive(Copy, Clone, Debug, Serialize, Deserialize, PartialEq)]
-#[serde(rename_all = "camelCase")]
-pub enum PlaceConditionalOrderType {
-    Stop {
-        trigger_price: Decimal,
-        order_price: Option<Decimal>,
-    },
-    TrailingStop {
-        trail_value: Decimal,
-    },
-    TakeProfit {
-        trigger_price: Decimal,
-        order_price: Option<Decimal>,
-    },
+/// A signed request, ready to be sent by either client.
+struct Prepared {
+    /// Milliseconds since the epoch the request was signed with.
+    timestamp: u64,
+    method: Method,
+    url: Url,
+    headers: HeaderMap,
+    /// The path relative to the endpoint, which identifies fixtures.
+    path: String,
+    params: Option<Map<String, Value>>,
+    body: String,
 }
 
-#[derive(Debug, Clone)]
-pub struct PlaceConditionalOrder {
-    pub market: String,
-    pub side: Side,
-    pub size: Decimal,
-    pub r#type: PlaceConditionalOrderType,
-    pub reduce_only: bool,
-    pub retry_until_filled: bool,
+impl Prepared {
+    fn sent_at(&self) -> DateTime<Utc> {
+        Utc.timestamp_millis_opt(self.timestamp as i64)
+            .single()
+            .unwrap_or_else(Utc::now)
+    }
 }
 
 impl Rest {
     pub const ENDPOINT: &'static str = "https://ftx.com/api";
     pub const ENDPOINT_US: &'static str = "https://ftx.us/api";
 
-    fn new_with_endpoint(
-        endpoint: &'static str,
+    /// How many requests bulk operations have in flight by default.
+    pub const DEFAULT_CONCURRENCY: usize = 10;
+
+    pub(crate) fn new_with_endpoint(
+        endpoint: &str,
         header_prefix: &'static str,
         key: String,
         secret: String,
         subaccount: Option<String>,
         proxy: Option<String>,
-    ) -> Self {
-        // Set default headers.
-        let mut headers = HeaderMap::new();
-        headers.insert(
-            HeaderName::from_str(&format!("{}-KEY", header_prefix)).unwrap(),
-            HeaderValue::from_str(&key).unwrap(),
-        );
-        if let Some(subaccount) = subaccount.to_owned() {
-            headers.insert(
-                HeaderName::from_str(&format!("{}-SUBACCOUNT", header_prefix)).unwrap(),
-                HeaderValue::from_str(&subaccount).unwrap(),
-            );
-        }
-
-        let mut client_builder = ClientBuilder::new().default_headers(headers);
+    ) -> Result<Self> {
+        let mut client_builder = ClientBuilder::new();
         if let Some(proxy) = proxy {
-            client_builder = client_builder.proxy(
-                reqwest::Proxy::all(format!("socks5h://{}", proxy)).expect("cannot parse proxy"),
-            );
+            client_builder =
+                client_builder.proxy(reqwest::Proxy::all(format!("socks5h://{}", proxy))?);
         }
 
-        let client = client_builder.build().unwrap();
+        let client = client_builder.build()?;
 
-        Self {
-            secret,
+        Ok(Self {
+            config: Config::new(endpoint, header_prefix, key, secret, subaccount),
             client,
-            subaccount,
-            endpoint,
-            header_prefix,
-        }
+        })
     }
 
+    /// Fails if the proxy address is invalid or the HTTP client cannot be
+    /// built.
     pub fn new(
         key: String,
         secret: String,
         subaccount: Option<String>,
         proxy: Option<String>,
-    ) -> Self {
+    ) -> Result<Self> {
         Self::new_with_endpoint(Self::ENDPOINT, "FTX", key, secret, subaccount, proxy)
     }
 
-    async fn get<T: DeserializeOwned + std::fmt::Debug>(
-        &self,
-        path: &str,
-        params: Option<Value>,
-    ) -> Result<T> {
-        self.request(Method::GET, path, params, None).await
+    /// Records every response to, or replays them from, the given fixture
+    /// directory. Replaying requires neither network access nor valid
+    /// credentials, which allows running tests offline.
+    pub fn with_fixtures(mut self, dir: impl Into<std::path::PathBuf>, mode: FixtureMode) -> Self {
+        self.config.fixtures = Some(Fixtures::new(dir, mode));
+        self
     }
 
-    async fn post<T: DeserializeOwned + std::fmt::Debug>(
-        &self,
-        path: &str,
-        body: Option<Value>,
-    ) -> Result<T> {
-        self.request(Method::POST, path, None, body).await
+    /// Returns a handle that acts on behalf of the given subaccount. It shares
+    /// the connection pool, credentials and clock of this client.
+    pub fn with_subaccount(&self, nickname: impl Into<String>) -> Self {
+        let mut rest = self.clone();
+        rest.config.subaccount = Some(nickname.into());
+        rest
     }
 
-    async fn delete<T: DeserializeOwned + std::fmt::Debug>(
-        &self,
-        path: &str,
-        body: Option<Value>,
-    ) -> Result<T> {
-        self.request(Method::DELETE, path, None, body).await
+    /// Returns a handle that acts on behalf of the main account.
+    pub fn without_subaccount(&self) -> Self {
+        let mut rest = self.clone();
+        rest.config.subaccount = None;
+        rest
     }
 
-    async fn request<T: DeserializeOwned + std::fmt::Debug>(
-        &self,
-        method: Method,
-        path: &str,
-        params: Option<Value>,
-        body: Option<Value>,
-    ) -> Result<T> {
-        let timestamp = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap()
-            .as_millis();
-        let body = if let Some(body) = body {
-            format!("{}", body)
-        } else {
-            String::new()
-        };
-        let url = format!("{}{}", self.endpoint, path);
-        let sign_payload = format!("{}{}/api{}{}", timestamp, method, path, body);
-        let sign = HMAC::mac(sign_payload.as_bytes(), self.secret.as_bytes());
-        let sign = hex::encode(sign);
-        let params = params.map(|value| {
-            if let Value::Object(map) = value {
-                map.into_iter()
-                    .filter(|(_, v)| v != &Value::Null)
-                    .collect::<Map<String, Value>>()
-            } else {
-                panic!("Invalid params.");
-            }
-        });
-
-        log::trace!("timestamp: {}", timestamp);
-        log::trace!("method: {}", method);
-        log::trace!("path: {}", path);
-        log::trace!("body: {}", body);
-
-        let mut headers = HeaderMap::new();
-        headers.insert(
-            reqwest::header::CONTENT_TYPE,
-            HeaderValue::from_static("application/json"),
-        );
-        headers.insert(
-            HeaderName::from_str(&format!("{}-TS", self.header_prefix)).unwrap(),
-            HeaderValue::from_str(&format!("{}", timestamp)).unwrap(),
-        );
-        headers.insert(
-            HeaderName::from_str(&format!("{}-SIGN", self.header_prefix)).unwrap(),
-            HeaderValue::from_str(&sign).unwrap(),
-        );
-        if let Some(subaccount) = &self.subaccount {
-            headers.insert(
-                HeaderName::from_str(&format!("{}-SUBACCOUNT", self.header_prefix)).unwrap(),
-                HeaderValue::from_str(subaccount).unwrap(),
-            );
-        }
-
-        /*
-        let response: String = self
-            .client
-            .request(method, url)
-            .query(&params)
-            .headers(headers)
-            .body(body)
-            .send()
-            .await?
-            .text()
-            .await?;
-
-        use std::fs::File;
-        use std::io::prelude::*;
-        let mut file = File::create("response.json").unwrap();
-        file.write_all(response.as_bytes()).unwrap();
-
-        panic!("{:#?}", response);
-        */
-
-        let body = self
-            .client
-            .request(method, url)
-            .query(&params)
-            .headers(headers)
-            .body(body)
-            .send()
-            .await?
-            .bytes()
-            .await?;
-
-        match from_reader(&*body) {
-            Ok(SuccessResponse { result, .. }) => {
-                log::trace!("{:?}", &result);
-                Ok(result)
-            }
-
-            Err(e) => {
-                if let Ok(ErrorResponse { error, .. }) = from_reader(&*body) {
-                    Err(Error::Api(error))
-                } else {
-                    let v: serde_json::Value = from_reader(&*body).unwrap();
-                    log::warn!("{}", v.to_string());
-                    Err(e.into())
-                }
-            }
-        }
+    pub fn subaccount(&self) -> Option<&str> {
+        self.config.subaccount.as_deref()
     }
 
-    pub async fn get_subaccounts(&self) -> Result<Subaccounts> {
-        self.get("/subaccounts", None).await
+    /// Replaces the rate limiter, which defaults to the FTX limit of 30
+    /// requests per second. It is shared by all clones of this client, so
+    /// a limiter can also be shared with clients created separately.
+    pub fn with_rate_limiter(mut self, rate_limiter: RateLimiter) -> Self {
+        self.config.rate_limiter = Some(rate_limiter);
+        self
     }
 
-    pub async fn create_subaccount(&self, nickname: &str) -> Result<Create> {
-        self.post(
-            "/subaccounts",
-            Some(json!({
-                "nickname": nickname,
-            })),
-        )
-        .await
+    /// Sends requests as fast as they are made, e.g. when rate-limiting
+    /// happens elsewhere.
+    pub fn without_rate_limiter(mut self) -> Self {
+        self.config.rate_limiter = None;
+        self
     }
 
-    pub async fn change_subaccount_name(
-        &self,
-        nickname: &str,
-        new_nickname: &str,
-    ) -> Result<ChangeName> {
-        self.post(
-            "/subaccounts/update_name",
-            Some(json!({
-                "nickname": nickname,
-                "newNickname": new_nickname,
-            })),
-        )
-        .await
-    }
-
-    pub async fn delete_subaccount(&self, nickname: &str) -> Result<Delete> {
-        self.delete(
-            "/subaccounts",
-            Some(json!({
-                "nickname": nickname,
-            })),
-        )
-        .await
-    }
-
-    pub async fn get_subaccount_balances(&self, nickname: &str) -> Result<Balances> {
-        self.get(&format!("/subaccounts/{}/balances", nickname), None)
-            .await
+    /// Sets how many requests bulk operations such as `place_orders` have in
+    /// flight at once.
+    pub fn with_concurrency(mut self, concurrency: usize) -> Self {
+        self.config.concurrency = concurrency.max(1);
+        self
     }
 
-    pub async fn transfer_between_subaccounts(
-        &self,
-        coin: &str,
-        size: Decimal,
-        source: &str,
-        destination: &str,
-    ) -> Result<Transfer> {
-        self.post(
-            "/subaccounts/transfer",
-            Some(json!({
-                "coin": coin,
-                "size": size,
-                "source": source,
-                "destination": destination,
-            })),
-        )
-        .await
-    }
-
-    pub async fn get_markets(&self) -> Result<Markets> {
-        self.get("/markets", None).await
-    }
-
-    pub async fn get_market(&self, market_name: &str) -> Result<Market> {
-        self.get(&format!("/markets/{}", market_name), None).await
-    }
-
-    pub async fn get_orderbook(&self, market_name: &str, depth: Option<u32>) -> Result<Orderbook> {
-        self.get(
-            &format!("/markets/{}/orderbook", market_name),
-            Some(json!({
-                "depth": depth,
-            })),
-        )
-        .await
-    }
-
-    pub async fn get_trades(
-        &self,
-        market_name: &str,
-        limit: Option<u32>,
-        start_time: Option<DateTime<Utc>>,
-        end_time: Option<DateTime<Utc>>,
-    ) -> Result<Trades> {
-        self.get(
-            &format!("/markets/{}/trades", market_name),
-            Some(json!({
-                "limit": limit,
-                "start_time": start_time.map(|t| t.timestamp()),
-                "end_time": end_time.map(|t| t.timestamp()),
-            })),
-        )
-        .await
-    }
-
-    pub async fn get_historical_prices(
-        &self,
-        market_name: &str,
-        resolution: u32,
-        limit: Option<u32>,
-        start_time: Option<DateTime<Utc>>,
-        end_time: Option<DateTime<Utc>>,
-    ) -> Result<Prices> {
-        self.get(
-            &format!("/markets/{}/candles", market_name),
-            Some(json!({
-                "resolution": resolution,
-                "limit": limit,
-                "start_time": start_time.map(|t| t.timestamp()),
-                "end_time": end_time.map(|t| t.timestamp()),
-            })),
-        )
-        .await
+    /// In dry-run mode, requests other than `GET` are built, signed and
+    /// logged, but not sent. They fail with `Error::DryRun` instead, which
+    /// allows running a strategy against live market data without trading.
+    pub fn with_dry_run(mut self, dry_run: bool) -> Self {
+        self.config.dry_run = dry_run;
+        self
     }
 
-    pub async fn get_futures(&self) -> Result<Futures> {
-        self.get("/futures", None).await
+    pub fn is_dry_run(&self) -> bool {
+        self.config.dry_run
     }
 
-    pub async fn get_future(&self, future_name: &str) -> Result<Future> {
-        self.get(&format!("/futures/{}", future_name), None).await
+    /// Uses a clock shared with other clients, e.g. a `Ws`, for timestamps.
+    pub fn with_clock(mut self, clock: Clock) -> Self {
+        self.config.clock = clock;
+        self
     }
 
-    pub async fn get_account(&self) -> Result<Account> {
-        self.get("/account", None).await
+    pub fn clock(&self) -> &Clock {
+        &self.config.clock
     }
 
-    pub async fn get_positions(&self) -> Result<Positions> {
-        self.get("/positions", None).await
+    /// Milliseconds the server clock is ahead of the local clock, as of the
+    /// last `sync_time`.
+    pub fn clock_skew(&self) -> i64 {
+        self.config.clock.offset()
     }
 
-    pub async fn get_coins(&self) -> Result<Vec<CoinInfo>> {
-        self.get("/wallet/coins", None).await
-    }
+    /// Measures the offset between the local and the server clock and applies
+    /// it to the timestamps of subsequent requests. Returns the offset in
+    /// milliseconds.
+    ///
+    /// The server time is read from the `/time` endpoint, or from the
+    /// second-resolution `Date` header when the endpoint is unavailable.
+    pub async fn sync_time(&self) -> Result<i64> {
+        let sent = local_millis();
+        let response = self
+            .client
+            .get(format!("{}/time", self.config.endpoint))
+            .send()
+            .await?;
+        let date = response.headers().get(reqwest::header::DATE).cloned();
+        let body = response.bytes().await?;
+        let received = local_millis();
 
-    pub async fn get_wallet_deposit_address(
-        &self,
-        coin: &str,
-        method: Option<&str>,
-    ) -> Result<WalletDepositAddress> {
-        self.get(
-            &format!(
-                "/wallet/deposit_address/{}{}",
-                coin,
-                if let Some(method) = method {
-                    format!("?method={}", method)
-                } else {
-                    "".to_string()
-                }
-            ),
-            None,
-        )
-        .await
+        self.config
+            .update_clock(sent, received, date.as_ref(), &body)
     }
 
-    pub async fn get_wallet_balances(&self) -> Result<Vec<WalletBalance>> {
-        self.get("/wallet/balances", None).await
+    /// Sends a request to its endpoint. Parameters of `GET` requests are
+    /// URL-encoded into the query string, those of other requests are sent as
+    /// the JSON body.
+    pub async fn send<R: Request>(&self, request: R) -> Result<R::Response> {
+        self.send_with_meta(request).await?.result
     }
 
-    pub async fn get_wallet_deposits(
-        &self,
-        limit: Option<usize>,
-        start_time: Option<DateTime<Utc>>,
-        end_time: Option<DateTime<Utc>>,
-    ) -> Result<Vec<WalletDeposit>> {
-        let mut params = vec![];
-        if let Some(limit) = limit {
-            params.push(format!("limit={}", limit));
-        }
-        if let Some(start_time) = start_time {
-            params.push(format!("start_time={}", start_time.timestamp()));
+    /// Like `send`, but also returns the HTTP status, headers and latency of
+    /// the response. Only failures to get a response are returned as `Err`,
+    /// errors reported by the API come with their metadata as well.
+    pub async fn send_with_meta<R: Request>(&self, request: R) -> Result<Response<R::Response>> {
+        let prepared = self.config.prepare_request(&request)?;
+        self.config.check_dry_run(&prepared)?;
+        if let Some(replayed) = self.config.replay(&prepared)? {
+            return Ok(replayed);
         }
-        if let Some(end_time) = end_time {
-            params.push(format!("end_time={}", end_time.timestamp()));
+        if let Some(rate_limiter) = &self.config.rate_limiter {
+            rate_limiter.acquire().await;
         }
 
-        self.get(
-            &format!(
-                "/wallet/deposits{}{}",
-                if params.is_empty() { "" } else { "?" },
-                params.join("&")
-            ),
-            None,
-        )
-        .await
-    }
+        let start = Instant::now();
+        let response = self
+            .client
+            .request(prepared.method.clone(), prepared.url.clone())
+            .headers(prepared.headers.clone())
+            .body(prepared.body.clone())
+            .send()
+            .await?;
+        let status = response.status();
+        let headers = response.headers().clone();
+        let body = response.bytes().await?;
+        let latency = start.elapsed();
 
-    pub async fn get_open_orders(&self, market: &str) -> Result<Vec<OrderInfo>> {
-        self.get(&format!("/orders?market={}", market), None).await
+        self.config
+            .finish(&prepared, status, headers, latency, &body)
     }
+}
 
-    pub async fn get_fills(
-        &self,
-        market: Option<&str>,
-        limit: Option<usize>,
-        order_id: Option<Id>,
-        start_time: Option<DateTime<Utc>>,
-        end_time: Option<DateTime<Utc>>,
-    ) -> Result<Vec<FillInfo>> {
-        let mut params = vec![];
-        if let Some(market) = market {
-            params.push(format!("market={}", market));
-        }
-        if let Some(limit) = limit {
-            params.push(format!("limit={}", limit));
-        }
-        if let Some(start_time) = start_time {
-            params.push(format!("start_time={}", start_time.timestamp()));
-        }
-        if let Some(end_time) = end_time {
-            params.push(format!("end_time={}", end_time.timestamp()));
-        }
-        if let Some(order_id) = order_id {
-            params.push(format!("orderId={}", order_id));
+impl Config {
+    fn new(
+        endpoint: &str,
+        header_prefix: &'static str,
+        key: String,
+        secret: String,
+        subaccount: Option<String>,
+    ) -> Self {
+        Self {
+            key,
+            signer: Arc::new(Signer::new(secret)),
+            clock: Clock::new(),
+            subaccount,
+            endpoint: endpoint.to_string(),
+            header_prefix,
+            fixtures: None,
+            rate_limiter: Some(RateLimiter::default()),
+            concurrency: Rest::DEFAULT_CONCURRENCY,
+            dry_run: false,
         }
-
-        self.get(
-            &format!(
-                "/fills{}{}",
-                if params.is_empty() { "" } else { "?" },
-                params.join("&")
-            ),
-            None,
-        )
-        .await
     }
 
-    pub async fn get_order_history(
+    /// Applies a server time, read from the response to a `/time` request
+    /// that was sent and received at the given local times.
+    fn update_clock(
         &self,
-        market: Option<&str>,
-        limit: Option<usize>,
-        start_time: Option<DateTime<Utc>>,
-        end_time: Option<DateTime<Utc>>,
-    ) -> Result<Vec<OrderInfo>> {
-        let mut params = vec![];
-        if let Some(market) = market {
-            params.push(format!("market={}", market));
-        }
-        if let Some(limit) = limit {
-            params.push(format!("limit={}", limit));
-        }
-        if let Some(start_time) = start_time {
-            params.push(format!("start_time={}", start_time.timestamp()));
-        }
-        if let Some(end_time) = end_time {
-            params.push(format!("end_time={}", end_time.timestamp()));
-        }
+        sent: u64,
+        received: u64,
+        date: Option<&HeaderValue>,
+        body: &[u8],
+    ) -> Result<i64> {
+        let server_time = match from_slice::<SuccessResponse<DateTime<Utc>>>(body) {
+            Ok(time) => time.result.timestamp_millis(),
+            // The header is truncated to the second, so assume the middle of it
+            Err(_) => match date
+                .and_then(|date| date.to_str().ok())
+                .and_then(|date| DateTime::parse_from_rfc2822(date).ok())
+            {
+                Some(date) => date.timestamp_millis() + 500,
+                None => return Err(Error::MissingServerTime),
+            },
+        };
 
-        self.get(
-            &format!(
-                "/orders/history{}{}",
-                if params.is_empty() { "" } else { "?" },
-                params.join("&")
-            ),
-            None,
-        )
-        .await
+        let offset = self.clock.update(sent, received, server_time.max(0) as u64);
+        log::debug!("clock skew: {}ms", offset);
+        Ok(offset)
     }
 
-    pub async fn get_conditional_order_history(
-        &self,
-        market: Option<&str>,
-        limit: Option<usize>,
-        start_time: Option<DateTime<Utc>>,
-        end_time: Option<DateTime<Utc>>,
-    ) -> Result<Vec<ConditionalOrderInfo>> {
-        let mut params = vec![];
-        if let Some(market) = market {
-            params.push(format!("market={}", market));
-        }
-        if let Some(limit) = limit {
-            params.push(format!("limit={}", limit));
-        }
-        if let Some(start_time) = start_time {
-            params.push(format!("start_time={}", start_time.timestamp()));
-        }
-        if let Some(end_time) = end_time {
-            params.push(format!("end_time={}", end_time.timestamp()));
+    fn prepare_request<R: Request>(&self, request: &R) -> Result<Prepared> {
+        let path = request.path();
+        let params = serde_json::to_value(request)?;
+        if R::METHOD == Method::GET {
+            self.prepare(R::METHOD, &path, Some(params), None)
+        } else {
+            self.prepare(R::METHOD, &path, None, Some(params))
         }
-
-        self.get(
-            &format!(
-                "/conditional_orders/history{}{}",
-                if params.is_empty() { "" } else { "?" },
-                params.join("&")
-            ),
-            None,
-        )
-        .await
     }
 
-    #[allow(clippy::too_many_arguments)]
-    pub async fn place_order(
+    /// Builds and signs a request.
+    fn prepare(
         &self,
-        market: &str,
-        side: Side,
-        price: Option<Decimal>,
-        r#type: OrderType,
-        size: Decimal,
-        reduce_only: Option<bool>,
-        ioc: Option<bool>,
-        post_only: Option<bool>,
-        client_id: Option<&str>,
-    ) -> Result<OrderInfo> {
-        // Limit orders should have price specified
-        if let OrderType::Limit = r#type {
-            if price.is_none() {
-                return Err(Error::PlacingLimitOrderRequiresPrice);
+        method: Method,
+        path: &str,
+        params: Option<Value>,
+        body: Option<Value>,
+    ) -> Result<Prepared> {
+        let timestamp = self.clock.now();
+        // Requests without parameters have neither a body nor a query string
+        let body = match body {
+            None | Some(Value::Null) => String::new(),
+            Some(Value::Object(map)) if map.is_empty() => String::new(),
+            Some(body) => format!("{}", body),
+        };
+        let params = match params {
+            None | Some(Value::Null) => None,
+            Some(Value::Object(map)) if map.is_empty() => None,
+            Some(Value::Object(map)) => Some(
+                map.into_iter()
+                    .filter(|(_, v)| v != &Value::Null)
+                    .collect::<Map<String, Value>>(),
+            ),
+            Some(value) => return Err(Error::InvalidParams(value)),
+        };
+        let mut url = Url::parse(&format!("{}{}", self.endpoint, path))?;
+        if let Some(params) = &params {
+            let mut query = url.query_pairs_mut();
+            for (key, value) in params {
+                match value {
+                    Value::String(value) => query.append_pair(key, value),
+                    value => query.append_pair(key, &value.to_string()),
+                };
             }
         }
+        // The signature covers the path and query string as sent
+        let sign = self.signer.sign_rest(
+            timestamp,
+            method.as_str(),
+            &url[url::Position::BeforePath..],
+            &body,
+        );
 
-        self.post(
-            "/orders",
-            Some(json!({
-                "market": market,
-                "side": side,
-                // As per docs, send null for market orders
-                "price": if let OrderType::Limit = r#type { price } else { None },
-                "type": r#type,
-                "size": size,
-                "reduceOnly": reduce_only.unwrap_or(false),
-                "ioc": ioc.unwrap_or(false),
-                "postOnly": post_only.unwrap_or(false),
-                "clientId": client_id,
-            })),
-        )
-        .await
-    }
-
-    pub async fn place_conditional_order(
-        &self,
-        info: PlaceConditionalOrder,
-    ) -> Result<ConditionalOrderInfo> {
-        let mut g_trigger_price = None;
-        let mut g_order_price = None;
-        let mut g_trail_value = None;
-        let mut g_type = None;
-        match info.r#type {
-            PlaceConditionalOrderType::Stop {
-                trigger_price,
-                order_price,
-            } => {
-                g_type = Some("stop");
-                g_trigger_price = Some(trigger_price);
-                g_order_price = order_price;
-            }
-            PlaceConditionalOrderType::TrailingStop { trail_value } => {
-                g_type = Some("trailingStop");
-                g_trail_value = Some(trail_value);
-            }
-            PlaceConditionalOrderType::TakeProfit {
-                trigger_price,
-                order_price,
-            } => {
-                g_type = Some("takeProfit");
-                g_trigger_price = Some(trigger_price);
-                g_order_price = order_price;
-            }
-        };
-        self.post(
-            "/conditional_orders",
-            Some(json!({
-                "market": info.market,
-                "side": info.side,
-                "size": info.size,
-                "type": g_type,
-                "reduceOnly": info.reduce_only,
-                "retryUntilFilled": info.retry_until_filled,
-                "triggerPrice": g_trigger_price,
-                "orderPrice": g_order_price,
-                "trailValue": g_trail_value
-            })),
-        )
-        .await
-    }
-
-    pub async fn modify_order(
-        &self,
-        order_id: Id,
-        price: Option<Decimal>,
-        size: Option<Decimal>,
-        client_id: Option<&str>,
-    ) -> Result<OrderInfo> {
-        self.post(
-            format!("/orders/{}/modify", order_id).as_str(),
-            Some(json!({
-                "price": price,
-                "size": size,
-                "clientId": client_id,
-            })),
-        )
-        .await
-    }
-
-    pub async fn modify_order_by_client_id(
-        &self,
-        client_id: &str,
-        price: Option<Decimal>,
-        size: Option<Decimal>,
-    ) -> Result<OrderInfo> {
-        self.post(
-            format!("/orders/by_client_id/{}/modify", client_id).as_str(),
-            Some(json!({
-                "price": price,
-                "size": size,
-                "clientId": client_id,
-            })),
-        )
-        .await
-    }
-
-    pub async fn get_order(&self, order_id: Id) -> Result<OrderInfo> {
-        self.get(&format!("/orders/{}", order_id), None).await
-    }
-
-    pub async fn get_order_by_client_id(&self, client_id: &str) -> Result<OrderInfo> {
-        self.get(&format!("/orders/by_client_id/{}", client_id), None)
-            .await
-    }
+        log::trace!("timestamp: {}", timestamp);
+        log::trace!("method: {}", method);
+        log::trace!("path: {}", path);
+        log::trace!("body: {}", body);
 
-    pub async fn cancel_all_orders(
-        &self,
-        market: Option<&str>,
-        side: Option<Side>,
-        conditional_orders_only: Option<bool>,
-        limit_orders_only: Option<bool>,
-    ) -> Result<String> {
-        let mut payload = Map::new();
-        if let Some(market) = market {
-            payload.insert("market".to_string(), Value::String(market.to_string()));
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            reqwest::header::CONTENT_TYPE,
+            HeaderValue::from_static("application/json"),
+        );
+        headers.insert(
+            HeaderName::from_str(&format!("{}-KEY", self.header_prefix))?,
+            HeaderValue::from_str(&self.key)?,
+        );
+        headers.insert(
+            HeaderName::from_str(&format!("{}-TS", self.header_prefix))?,
+            HeaderValue::from_str(&format!("{}", timestamp))?,
+        );
+        headers.insert(
+            HeaderName::from_str(&format!("{}-SIGN", self.header_prefix))?,
+            HeaderValue::from_str(&sign)?,
+        );
+        if let Some(subaccount) = &self.subaccount {
+            headers.insert(
+                HeaderName::from_str(&format!("{}-SUBACCOUNT", self.header_prefix))?,
+                HeaderValue::from_str(subaccount)?,
+            );
         }
 
-        if let Some(side) = side {
-            payload.insert("side".to_string(), Value::String(to_string(&side).unwrap()));
+        Ok(Prepared {
+            timestamp,
+            method,
+            url,
+            headers,
+            path: path.to_string(),
+            params,
+            body,
+        })
+    }
+
+    /// Stops requests that would change anything when in dry-run mode.
+    fn check_dry_run(&self, prepared: &Prepared) -> Result<()> {
+        if !self.dry_run || prepared.method == Method::GET {
+            return Ok(());
         }
 
-        payload.insert(
-            "conditionalOrdersOnly".to_string(),
-            Value::Bool(conditional_orders_only.unwrap_or(false)),
+        log::info!(
+            "dry run: {} {} {}",
+            prepared.method,
+            prepared.url,
+            prepared.body
         );
-        payload.insert(
-            "limitOrdersOnly".to_string(),
-            Value::Bool(limit_orders_only.unwrap_or(false)),
+        Err(Error::DryRun {
+            method: prepared.method.clone(),
+            url: prepared.url.to_string(),
+            body: prepared.body.clone(),
+        })
+    }
+
+    /// Returns the recorded response to a request when replaying fixtures.
+    /// Fixtures do not record headers or latency, so these are empty.
+    fn replay<T: DeserializeOwned + std::fmt::Debug>(
+        &self,
+        prepared: &Prepared,
+    ) -> Result<Option<Response<T>>> {
+        let fixtures = match &self.fixtures {
+            Some(fixtures) if fixtures.mode() == FixtureMode::Replay => fixtures,
+            _ => return Ok(None),
+        };
+        let (status, body) = fixtures.load(
+            &prepared.method,
+            &prepared.path,
+            &prepared.params,
+            &prepared.body,
+        )?;
+
+        Ok(Some(Response {
+            result: parse_response(status, &body),
+            status,
+            headers: HeaderMap::new(),
+            sent_at: prepared.sent_at(),
+            latency: Duration::ZERO,
+        }))
+    }
+
+    /// Records the response to a request if fixtures are set, then parses it.
+    fn finish<T: DeserializeOwned + std::fmt::Debug>(
+        &self,
+        prepared: &Prepared,
+        status: StatusCode,
+        headers: HeaderMap,
+        latency: Duration,
+        body: &[u8],
+    ) -> Result<Response<T>> {
+        if let Some(fixtures) = &self.fixtures {
+            fixtures.save(
+                &prepared.method,
+                &prepared.path,
+                &prepared.params,
+                &prepared.body,
+                status,
+                body,
+            )?;
+        }
+        log::debug!(
+            "{} {}: HTTP {} in {:?}",
+            prepared.method,
+            prepared.path,
+            status,
+            latency
         );
 
-        self.delete("/orders", Some(Value::Object(payload))).await
+        Ok(Response {
+            result: parse_response(status, body),
+            status,
+            headers,
+            sent_at: prepared.sent_at(),
+            latency,
+        })
     }
+}
 
-    pub async fn cancel_order(&self, order_id: Id) -> Result<String> {
-        self.delete(&format!("/orders/{}", order_id), None).await
+impl Rest {
+    endpoints!(async, .await);
+
+    /// Like `place_order`, but also returns the metadata of the response,
+    /// e.g. to measure the latency of order entry.
+    pub async fn place_order_with_meta(&self, order: OrderRequest) -> Result<Response<OrderInfo>> {
+        order.validate()?;
+        self.send_with_meta(order).await
+    }
+
+    /// Places orders concurrently, see `with_concurrency`. The results are
+    /// in the order of `orders`, each either the placed order or the reason
+    /// it was not placed.
+    pub async fn place_orders(&self, orders: Vec<OrderRequest>) -> Vec<Result<OrderInfo>> {
+        stream::iter(orders)
+            .map(|order| self.place_order(order))
+            .buffered(self.config.concurrency)
+            .collect()
+            .await
     }
 
-    pub async fn cancel_order_by_client_id(&self, client_id: &str) -> Result<String> {
-        self.delete(&dbg!(format!("/orders/by_client_id/{}", client_id)), None)
+    /// Cancels orders concurrently, with results in the order of `order_ids`.
+    pub async fn cancel_orders(&self, order_ids: Vec<Id>) -> Vec<Result<String>> {
+        stream::iter(order_ids)
+            .map(|order_id| self.cancel_order(order_id))
+            .buffered(self.config.concurrency)
+            .collect()
             .await
     }
 
-    pub async fn get_funding_payments(
-        &self,
-        future: Option<&str>,
-        limit: Option<u32>,
-        start_time: Option<DateTime<Utc>>,
-        end_time: Option<DateTime<Utc>>,
-    ) -> Result<Vec<FundingPayment>> {
-        let mut params = vec![];
-        if let Some(future) = future {
-            params.push(format!("future={}", future));
-        }
-        if let Some(limit) = limit {
-            params.push(format!("limit={}", limit));
-        }
-        if let Some(start_time) = start_time {
-            params.push(format!("start_time={}", start_time.timestamp()));
+    /// Modifies orders concurrently, with results in the order of
+    /// `modifications`.
+    pub async fn modify_orders(&self, modifications: Vec<ModifyOrder>) -> Vec<Result<OrderInfo>> {
+        stream::iter(modifications)
+            .map(|modification| self.send(modification))
+            .buffered(self.config.concurrency)
+            .collect()
+            .await
+    }
+}
+
+/// Maximum number of bytes of a raw response body that are kept in an error.
+const MAX_ERROR_BODY_LEN: usize = 512;
+
+/// Parses the body of a response into either its result or an error.
+/// Bodies that cannot be parsed, e.g. HTML error pages served by a CDN
+/// during an outage, are reported together with their HTTP status.
+fn parse_response<T: DeserializeOwned + std::fmt::Debug>(
+    status: StatusCode,
+    body: &[u8],
+) -> Result<T> {
+    match from_slice(body) {
+        Ok(SuccessResponse { result, .. }) => {
+            log::trace!("{:?}", &result);
+            Ok(result)
         }
-        if let Some(end_time) = end_time {
-            params.push(format!("end_time={}", end_time.timestamp()));
+
+        Err(e) => {
+            if let Ok(ErrorResponse { error, .. }) = from_slice(body) {
+                Err(Error::Api(error))
+            } else {
+                let body = truncate_body(body);
+                log::warn!("HTTP {}: {}", status, body);
+                Err(Error::UnexpectedResponse {
+                    status,
+                    body,
+                    source: e,
+                })
+            }
         }
+    }
+}
 
-        self.get(
-            &format!(
-                "/funding_payments{}{}",
-                if params.is_empty() { "" } else { "?" },
-                params.join("&")
-            ),
-            None,
-        )
-        .await
+/// Lossily decodes a raw body, cutting it off after `MAX_ERROR_BODY_LEN` bytes.
+fn truncate_body(body: &[u8]) -> String {
+    let body = String::from_utf8_lossy(body);
+    if body.len() <= MAX_ERROR_BODY_LEN {
+        return body.into_owned();
+    }
+
+    let mut end = MAX_ERROR_BODY_LEN;
+    while !body.is_char_boundary(end) {
+        end -= 1;
     }
+    format!("{}...", &body[..end])
 }
@@ -1,18 +1,51 @@
-use chrono::{DateTime, Utc};
+use super::{Error, MarketSymbol, Result};
+use chrono::{DateTime, Duration, Utc};
 use rust_decimal::prelude::*;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::convert::{TryFrom, TryInto};
+use std::sync::Mutex;
 
 pub type Id = i64;
 pub type Coin = String;
 pub type Symbol = String;
 
-#[derive(Clone, Debug, Deserialize)]
+/// Deserializes the `Unknown` variant of enums that FTX may extend, logging a
+/// warning the first time each value is received instead of failing.
+pub(crate) fn deserialize_unknown<'de, T, D>(
+    deserializer: D,
+) -> std::result::Result<String, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let value = String::deserialize(deserializer)?;
+    let type_name = std::any::type_name::<T>();
+    if first_unknown(type_name, &value) {
+        log::warn!(
+            "Unknown {} {:?}, please update the ftx crate",
+            type_name,
+            value
+        );
+    }
+    Ok(value)
+}
+
+/// Whether `value` is the first of its kind, so that streams which repeat an
+/// unknown value do not log it with every message.
+pub(crate) fn first_unknown(type_name: &'static str, value: &str) -> bool {
+    static SEEN: Mutex<BTreeSet<(&str, String)>> = Mutex::new(BTreeSet::new());
+    // A poisoned set is still valid, at worst a value is logged twice
+    let mut seen = SEEN.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    seen.insert((type_name, value.to_string()))
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
 pub struct SuccessResponse<T> {
     pub success: bool,
     pub result: T,
 }
 
-#[derive(Clone, Debug, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
 pub struct ErrorResponse {
     pub success: bool,
     pub error: String,
@@ -20,7 +53,7 @@ pub struct ErrorResponse {
 
 // REST API -> Subaccounts
 
-#[derive(Clone, Debug, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct Subaccount {
     pub nickname: String,
@@ -31,7 +64,7 @@ pub struct Subaccount {
 
 pub type Subaccounts = Vec<Subaccount>;
 
-#[derive(Clone, Debug, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct Create {
     pub nickname: String,
@@ -39,15 +72,15 @@ pub struct Create {
     pub editable: bool,
 }
 
-#[derive(Copy, Clone, Debug, Deserialize)]
+#[derive(Copy, Clone, Debug, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct ChangeName;
 
-#[derive(Copy, Clone, Debug, Deserialize)]
+#[derive(Copy, Clone, Debug, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct Delete;
 
-#[derive(Clone, Debug, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct Balance {
     pub coin: Coin,
@@ -59,7 +92,7 @@ pub struct Balance {
 
 pub type Balances = Vec<Balance>;
 
-#[derive(Clone, Debug, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct Transfer {
     pub id: Id,
@@ -71,14 +104,18 @@ pub struct Transfer {
 
 // REST API -> Markets
 
-#[derive(Copy, Clone, Debug, Deserialize, PartialEq)]
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub enum MarketType {
     Future,
     Spot,
+    /// A value this version does not know yet. A warning is logged the first
+    /// time each one is received.
+    #[serde(untagged, deserialize_with = "deserialize_unknown::<Self, _>")]
+    Unknown(String),
 }
 
-#[derive(Clone, Debug, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct Market {
     #[serde(rename = "type")]
@@ -105,24 +142,63 @@ pub struct Market {
     pub volume_usd24h: Decimal,
 }
 
+impl Market {
+    /// Parses the name of the market.
+    pub fn symbol(&self) -> Result<MarketSymbol> {
+        self.name.parse()
+    }
+
+    /// Rounds a price to the price increment of the market, towards the
+    /// passive side: down for buys and up for sells.
+    pub fn round_price(&self, price: Decimal, side: Side) -> Decimal {
+        match side {
+            Side::Buy => round_down(price, self.price_increment),
+            Side::Sell => round_up(price, self.price_increment),
+        }
+    }
+
+    /// Rounds a size down to the size increment of the market.
+    pub fn round_size(&self, size: Decimal) -> Decimal {
+        round_down(size, self.size_increment)
+    }
+}
+
+fn round_down(value: Decimal, increment: Decimal) -> Decimal {
+    if increment.is_zero() {
+        return value;
+    }
+    (value / increment).floor() * increment
+}
+
+fn round_up(value: Decimal, increment: Decimal) -> Decimal {
+    if increment.is_zero() {
+        return value;
+    }
+    (value / increment).ceil() * increment
+}
+
 pub type Markets = Vec<Market>;
 
-#[derive(Clone, Debug, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct Orderbook {
     pub asks: Vec<(Decimal, Decimal)>,
     pub bids: Vec<(Decimal, Decimal)>,
 }
 
-#[derive(Copy, Clone, Debug, Serialize, Deserialize, PartialEq, sqlx::Type)]
+#[derive(Copy, Clone, Debug, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "postgres", derive(sqlx::Type))]
 #[serde(rename_all = "camelCase")]
-#[sqlx(type_name = "side", rename_all = "snake_case")]
+#[cfg_attr(
+    feature = "postgres",
+    sqlx(type_name = "side", rename_all = "snake_case")
+)]
 pub enum Side {
     Buy,
     Sell,
 }
 
-#[derive(Copy, Clone, Debug, Deserialize)]
+#[derive(Copy, Clone, Debug, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct Trade {
     pub id: Id,
@@ -135,7 +211,8 @@ pub struct Trade {
 
 pub type Trades = Vec<Trade>;
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "postgres", derive(sqlx::FromRow))]
 #[serde(rename_all = "camelCase")]
 pub struct FundingPayment {
     pub id: Id,
@@ -144,7 +221,7 @@ pub struct FundingPayment {
     pub time: DateTime<Utc>,
 }
 
-#[derive(Copy, Clone, Debug, Deserialize)]
+#[derive(Copy, Clone, Debug, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct Price {
     pub close: Decimal,
@@ -157,18 +234,124 @@ pub struct Price {
 
 pub type Prices = Vec<Price>;
 
+/// The window length of the candles returned by `get_historical_prices`.
+/// It is sent and (de)serialized as a number of seconds.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(into = "u32", try_from = "u32")]
+pub enum Resolution {
+    FifteenSeconds,
+    OneMinute,
+    FiveMinutes,
+    FifteenMinutes,
+    OneHour,
+    FourHours,
+    /// A multiple of a day, see `Resolution::days`.
+    Days(Days),
+}
+
+/// A number of days from 1 to 30, the longest resolution FTX supports.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Days(u8);
+
+impl Days {
+    pub const fn get(self) -> u32 {
+        self.0 as u32
+    }
+}
+
+impl Resolution {
+    pub const ONE_DAY: Self = Self::Days(Days(1));
+
+    /// Fails unless `days` is from 1 to 30.
+    pub fn days(days: u32) -> Result<Self> {
+        if !(1..=30).contains(&days) {
+            return Err(Error::InvalidResolution(i64::from(days) * 86400));
+        }
+        Ok(Self::Days(Days(days as u8)))
+    }
+
+    pub const fn seconds(self) -> u32 {
+        match self {
+            Self::FifteenSeconds => 15,
+            Self::OneMinute => 60,
+            Self::FiveMinutes => 300,
+            Self::FifteenMinutes => 900,
+            Self::OneHour => 3600,
+            Self::FourHours => 14400,
+            // `Resolution::days` only allows 1 to 30 days, at most 2592000 seconds
+            Self::Days(days) => days.get() * 86400,
+        }
+    }
+
+    pub fn duration(self) -> Duration {
+        Duration::seconds(self.seconds().into())
+    }
+}
+
+impl From<Resolution> for u32 {
+    fn from(resolution: Resolution) -> Self {
+        resolution.seconds()
+    }
+}
+
+impl From<Resolution> for Duration {
+    fn from(resolution: Resolution) -> Self {
+        resolution.duration()
+    }
+}
+
+impl TryFrom<u32> for Resolution {
+    type Error = Error;
+
+    fn try_from(seconds: u32) -> Result<Self> {
+        Ok(match seconds {
+            15 => Self::FifteenSeconds,
+            60 => Self::OneMinute,
+            300 => Self::FiveMinutes,
+            900 => Self::FifteenMinutes,
+            3600 => Self::OneHour,
+            14400 => Self::FourHours,
+            _ => {
+                let days = seconds / 86400;
+                if days * 86400 != seconds {
+                    return Err(Error::InvalidResolution(seconds.into()));
+                }
+                Self::days(days)?
+            }
+        })
+    }
+}
+
+impl TryFrom<Duration> for Resolution {
+    type Error = Error;
+
+    fn try_from(duration: Duration) -> Result<Self> {
+        let seconds = duration.num_seconds();
+        if duration != Duration::seconds(seconds) {
+            return Err(Error::InvalidResolution(seconds));
+        }
+        u32::try_from(seconds)
+            .map_err(|_| Error::InvalidResolution(seconds))?
+            .try_into()
+    }
+}
+
 // REST API -> Futures
 
-#[derive(Copy, Clone, Debug, Deserialize, PartialEq)]
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub enum FutureType {
     Future,
     Perpetual,
     Prediction,
     Move,
+    /// A value this version does not know yet. A warning is logged the first
+    /// time each one is received.
+    #[serde(untagged, deserialize_with = "deserialize_unknown::<Self, _>")]
+    Unknown(String),
 }
 
-#[derive(Clone, Debug, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct Future {
     pub ask: Option<Decimal>,
@@ -199,9 +382,16 @@ pub struct Future {
     pub market_type: FutureType,
 }
 
+impl Future {
+    /// Parses the name of the future.
+    pub fn symbol(&self) -> Result<MarketSymbol> {
+        self.name.parse()
+    }
+}
+
 pub type Futures = Vec<Future>;
 
-#[derive(Copy, Clone, Debug, Deserialize)]
+#[derive(Copy, Clone, Debug, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct FutureStats {
     pub volume: Decimal,
@@ -213,7 +403,7 @@ pub struct FutureStats {
     pub open_interest: Decimal,
 }
 
-#[derive(Clone, Debug, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct FundingRate {
     pub future: Symbol,
@@ -227,7 +417,7 @@ pub type FundingRates = Vec<FundingRate>;
 
 /// Returned by GET /account.
 /// See https://docs.ftx.com/#get-account-information.
-#[derive(Clone, Debug, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct Account {
     pub backstop_provider: bool,
@@ -257,7 +447,7 @@ pub struct Account {
 /// See https://docs.ftx.com/#get-positions.
 pub type Positions = Vec<Position>;
 
-#[derive(Clone, Debug, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct Position {
     pub cost: Decimal,
@@ -280,7 +470,7 @@ pub struct Position {
 // REST API -> Wallet
 // TODO
 
-#[derive(Clone, Debug, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct CoinInfo {
     pub id: Coin,     // "USDT"
@@ -307,14 +497,14 @@ pub struct CoinInfo {
     pub index_price: Decimal, // Not documented; note that ~8% return 1e-8
 }
 
-#[derive(Clone, Debug, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct WalletDepositAddress {
     pub address: String,
     pub tag: Option<String>,
 }
 
-#[derive(Clone, Debug, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct WalletBalance {
     pub coin: String,
@@ -327,16 +517,20 @@ pub struct WalletBalance {
     pub usd_value: Option<Decimal>,
 }
 
-#[derive(Copy, Clone, Debug, Serialize, Deserialize, PartialEq, sqlx::Type)]
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub enum DepositStatus {
     Confirmed,
     Unconfirmed,
     Cancelled,
     Complete,
+    /// A value this version does not know yet. A warning is logged the first
+    /// time each one is received.
+    #[serde(untagged, deserialize_with = "deserialize_unknown::<Self, _>")]
+    Unknown(String),
 }
 
-#[derive(Clone, Debug, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct WalletDeposit {
     pub id: Id,
@@ -354,34 +548,57 @@ pub struct WalletDeposit {
 // REST API -> Orders
 // TODO
 
-#[derive(Copy, Clone, Debug, Serialize, Deserialize, PartialEq, sqlx::Type)]
+#[derive(Copy, Clone, Debug, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "postgres", derive(sqlx::Type))]
 #[serde(rename_all = "camelCase")]
-#[sqlx(type_name = "order_type", rename_all = "snake_case")]
+#[cfg_attr(
+    feature = "postgres",
+    sqlx(type_name = "order_type", rename_all = "snake_case")
+)]
 pub enum OrderType {
     Market,
     Limit,
 }
 
-#[derive(Copy, Clone, Debug, Serialize, Deserialize, PartialEq, sqlx::Type)]
+#[derive(Copy, Clone, Debug, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "postgres", derive(sqlx::Type))]
 #[serde(rename_all = "snake_case")]
-#[sqlx(type_name = "conditional_order_type", rename_all = "snake_case")]
+#[cfg_attr(
+    feature = "postgres",
+    sqlx(type_name = "conditional_order_type", rename_all = "snake_case")
+)]
 pub enum ConditionalOrderType {
     Stop,
     TrailingStop,
     TakeProfit,
 }
 
-#[derive(Copy, Clone, Debug, Serialize, Deserialize, PartialEq, sqlx::Type)]
+#[derive(Copy, Clone, Debug, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "postgres", derive(sqlx::Type))]
 #[serde(rename_all = "camelCase")]
-#[sqlx(type_name = "fill_type", rename_all = "snake_case")]
+#[cfg_attr(
+    feature = "postgres",
+    sqlx(type_name = "fill_type", rename_all = "snake_case")
+)]
 pub enum FillType {
     Otc,
     Order,
 }
 
-#[derive(Copy, Clone, Debug, Serialize, Deserialize, PartialEq, sqlx::Type)]
+#[derive(Copy, Clone, Debug, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "postgres", derive(sqlx::Type))]
+#[serde(rename_all = "camelCase")]
+#[cfg_attr(
+    feature = "postgres",
+    sqlx(type_name = "liquidity", rename_all = "snake_case")
+)]
+pub enum Liquidity {
+    Maker,
+    Taker,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "camelCase")]
-#[sqlx(type_name = "order_status", rename_all = "snake_case")]
 /// Represents the status of the order.
 /// However, the REST and websockets APIs assign these values differently.
 ///
@@ -396,6 +613,7 @@ pub enum FillType {
 /// - Call the `get_order` REST API to see if the order status has been updated
 /// - Listen to orders over websockets to be notified of the update order status
 ///   as soon as it is available.
+///
 /// To get near-immediate feedback on the status of possibly-rejected orders,
 /// we recommend subscribing to the `Orders` channel over websockets.
 ///
@@ -417,18 +635,27 @@ pub enum OrderStatus {
     Open,
     /// Rest: filled or cancelled; Ws: filled, rejected, or cancelled
     Closed,
+    /// A value this version does not know yet. A warning is logged the first
+    /// time each one is received.
+    #[serde(untagged, deserialize_with = "deserialize_unknown::<Self, _>")]
+    Unknown(String),
 }
 
-#[derive(Copy, Clone, Debug, Serialize, Deserialize, PartialEq, sqlx::Type)]
+#[derive(Copy, Clone, Debug, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "postgres", derive(sqlx::Type))]
 #[serde(rename_all = "camelCase")]
-#[sqlx(type_name = "conditional_order_status", rename_all = "snake_case")]
+#[cfg_attr(
+    feature = "postgres",
+    sqlx(type_name = "conditional_order_status", rename_all = "snake_case")
+)]
 pub enum ConditionalOrderStatus {
     Open,
     Cancelled,
     Triggered,
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "postgres", derive(sqlx::FromRow))]
 #[serde(rename_all = "camelCase")]
 pub struct OrderInfo {
     pub id: Id,
@@ -450,7 +677,8 @@ pub struct OrderInfo {
     pub client_id: Option<String>,
 }
 
-#[derive(Clone, Debug, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "postgres", derive(sqlx::FromRow))]
 #[serde(rename_all = "camelCase")]
 pub struct ConditionalOrderInfo {
     pub id: Id,
@@ -474,7 +702,8 @@ pub struct ConditionalOrderInfo {
     pub retry_until_filled: bool,
 }
 
-#[derive(Clone, Debug, Deserialize, sqlx::FromRow)]
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "postgres", derive(sqlx::FromRow))]
 #[serde(rename_all = "camelCase")]
 pub struct FillInfo {
     pub id: Id,
@@ -488,7 +717,7 @@ pub struct FillInfo {
     pub fee: Decimal,
     pub fee_currency: Coin,
     pub fee_rate: Decimal,
-    pub liquidity: String,
+    pub liquidity: Liquidity,
     pub base_currency: Option<Coin>,
     pub quote_currency: Option<Coin>,
     pub order_id: Option<Id>,
@@ -0,0 +1,69 @@
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+/// Limits how many requests are sent within a sliding window. Clones share
+/// the window, so all handles of a `Rest` client count against one limit.
+#[derive(Clone, Debug)]
+pub struct RateLimiter {
+    limit: usize,
+    period: Duration,
+    sent: Arc<Mutex<VecDeque<Instant>>>,
+}
+
+impl RateLimiter {
+    /// FTX answers with HTTP 429 to more than 30 requests per second.
+    /// See https://docs.ftx.com/#rate-limits
+    pub const FTX_LIMIT: usize = 30;
+
+    pub fn new(limit: usize, period: Duration) -> Self {
+        Self {
+            limit: limit.max(1),
+            period,
+            sent: Arc::new(Mutex::new(VecDeque::new())),
+        }
+    }
+
+    /// Takes a slot if one is free, otherwise returns how long to wait
+    /// before trying again.
+    fn try_acquire(&self) -> Option<Duration> {
+        let now = Instant::now();
+        let mut sent = self.sent.lock().unwrap_or_else(|e| e.into_inner());
+        while sent
+            .front()
+            .is_some_and(|&t| now.duration_since(t) >= self.period)
+        {
+            sent.pop_front();
+        }
+
+        if sent.len() < self.limit {
+            sent.push_back(now);
+            None
+        } else {
+            sent.front()
+                .map(|&oldest| self.period.saturating_sub(now.duration_since(oldest)))
+        }
+    }
+
+    /// Waits until a request may be sent.
+    pub async fn acquire(&self) {
+        while let Some(wait) = self.try_acquire() {
+            tokio::time::sleep(wait).await;
+        }
+    }
+
+    /// Blocks the thread until a request may be sent.
+    pub fn acquire_blocking(&self) {
+        while let Some(wait) = self.try_acquire() {
+            std::thread::sleep(wait);
+        }
+    }
+}
+
+impl Default for RateLimiter {
+    fn default() -> Self {
+        Self::new(Self::FTX_LIMIT, Duration::from_secs(1))
+    }
+}
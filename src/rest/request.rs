@@ -0,0 +1,906 @@
+use super::error::{Error, Result};
+use super::model::*;
+use chrono::{DateTime, TimeZone, Utc};
+use percent_encoding::{utf8_percent_encode, AsciiSet, CONTROLS};
+pub use reqwest::Method;
+use rust_decimal::prelude::*;
+use serde::{de::DeserializeOwned, Deserialize, Serialize, Serializer};
+use serde_with::{serde_as, TimestampSeconds};
+use std::fmt::Debug;
+
+/// An endpoint of the REST API, sent with `Rest::send`.
+///
+/// The fields of a request are its parameters: they are serialized to the
+/// query string of `GET` requests and to the JSON body of all other requests.
+/// Fields that are part of the path should be `#[serde(skip)]` and encoded
+/// with `encode_segment` or `encode_market` in `path`.
+///
+/// Endpoints that this crate does not wrap yet can be added by implementing
+/// this trait:
+///
+/// ```
+/// use ftx::rest::{Request, Method};
+/// use serde::{Deserialize, Serialize};
+///
+/// #[derive(Debug, Serialize)]
+/// struct GetLendingRates;
+///
+/// #[derive(Debug, Deserialize)]
+/// #[serde(rename_all = "camelCase")]
+/// struct LendingRate {
+///     coin: String,
+///     previous: f64,
+///     estimate: f64,
+/// }
+///
+/// impl Request for GetLendingRates {
+///     const METHOD: Method = Method::GET;
+///     type Response = Vec<LendingRate>;
+///
+///     fn path(&self) -> String {
+///         "/spot_margin/lending_rates".to_string()
+///     }
+/// }
+/// ```
+pub trait Request: Serialize {
+    const METHOD: Method;
+    type Response: DeserializeOwned + Debug;
+
+    /// The path relative to the API endpoint, e.g. `/markets`.
+    fn path(&self) -> String;
+}
+
+/// Characters that have to be escaped within a path segment.
+const SEGMENT: &AsciiSet = &CONTROLS
+    .add(b' ')
+    .add(b'"')
+    .add(b'#')
+    .add(b'%')
+    .add(b'/')
+    .add(b'<')
+    .add(b'>')
+    .add(b'?')
+    .add(b'`')
+    .add(b'{')
+    .add(b'}');
+
+/// Percent-encodes a single path segment, such as a client order ID.
+pub fn encode_segment(segment: &str) -> String {
+    utf8_percent_encode(segment, SEGMENT).to_string()
+}
+
+/// Percent-encodes a market name. The slash of spot markets such as
+/// `BTC/USD` is kept, since FTX expects it to separate path segments.
+pub fn encode_market(market: &str) -> String {
+    market
+        .split('/')
+        .map(encode_segment)
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+// REST API -> Subaccounts
+
+#[derive(Clone, Debug, Serialize)]
+pub struct GetSubaccounts;
+
+impl Request for GetSubaccounts {
+    const METHOD: Method = Method::GET;
+    type Response = Subaccounts;
+
+    fn path(&self) -> String {
+        "/subaccounts".to_string()
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct CreateSubaccount {
+    pub nickname: String,
+}
+
+impl Request for CreateSubaccount {
+    const METHOD: Method = Method::POST;
+    type Response = Create;
+
+    fn path(&self) -> String {
+        "/subaccounts".to_string()
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChangeSubaccountName {
+    pub nickname: String,
+    pub new_nickname: String,
+}
+
+impl Request for ChangeSubaccountName {
+    const METHOD: Method = Method::POST;
+    type Response = ChangeName;
+
+    fn path(&self) -> String {
+        "/subaccounts/update_name".to_string()
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct DeleteSubaccount {
+    pub nickname: String,
+}
+
+impl Request for DeleteSubaccount {
+    const METHOD: Method = Method::DELETE;
+    type Response = Delete;
+
+    fn path(&self) -> String {
+        "/subaccounts".to_string()
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct GetSubaccountBalances {
+    #[serde(skip)]
+    pub nickname: String,
+}
+
+impl Request for GetSubaccountBalances {
+    const METHOD: Method = Method::GET;
+    type Response = Balances;
+
+    fn path(&self) -> String {
+        format!("/subaccounts/{}/balances", encode_segment(&self.nickname))
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct TransferBetweenSubaccounts {
+    pub coin: Coin,
+    pub size: Decimal,
+    pub source: String,
+    pub destination: String,
+}
+
+impl Request for TransferBetweenSubaccounts {
+    const METHOD: Method = Method::POST;
+    type Response = Transfer;
+
+    fn path(&self) -> String {
+        "/subaccounts/transfer".to_string()
+    }
+}
+
+// REST API -> Markets
+
+#[derive(Clone, Debug, Serialize)]
+pub struct GetMarkets;
+
+impl Request for GetMarkets {
+    const METHOD: Method = Method::GET;
+    type Response = Markets;
+
+    fn path(&self) -> String {
+        "/markets".to_string()
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct GetMarket {
+    #[serde(skip)]
+    pub market_name: String,
+}
+
+impl Request for GetMarket {
+    const METHOD: Method = Method::GET;
+    type Response = Market;
+
+    fn path(&self) -> String {
+        format!("/markets/{}", encode_market(&self.market_name))
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct GetOrderbook {
+    #[serde(skip)]
+    pub market_name: String,
+    pub depth: Option<u32>,
+}
+
+impl Request for GetOrderbook {
+    const METHOD: Method = Method::GET;
+    type Response = Orderbook;
+
+    fn path(&self) -> String {
+        format!("/markets/{}/orderbook", encode_market(&self.market_name))
+    }
+}
+
+#[serde_as]
+#[derive(Clone, Debug, Serialize)]
+pub struct GetTrades {
+    #[serde(skip)]
+    pub market_name: String,
+    pub limit: Option<u32>,
+    #[serde_as(as = "Option<TimestampSeconds<i64>>")]
+    pub start_time: Option<DateTime<Utc>>,
+    #[serde_as(as = "Option<TimestampSeconds<i64>>")]
+    pub end_time: Option<DateTime<Utc>>,
+}
+
+impl Request for GetTrades {
+    const METHOD: Method = Method::GET;
+    type Response = Trades;
+
+    fn path(&self) -> String {
+        format!("/markets/{}/trades", encode_market(&self.market_name))
+    }
+}
+
+#[serde_as]
+#[derive(Clone, Debug, Serialize)]
+pub struct GetHistoricalPrices {
+    #[serde(skip)]
+    pub market_name: String,
+    pub resolution: Resolution,
+    pub limit: Option<u32>,
+    #[serde_as(as = "Option<TimestampSeconds<i64>>")]
+    pub start_time: Option<DateTime<Utc>>,
+    #[serde_as(as = "Option<TimestampSeconds<i64>>")]
+    pub end_time: Option<DateTime<Utc>>,
+}
+
+impl GetHistoricalPrices {
+    /// The most candles FTX returns for one request.
+    pub const MAX_CANDLES: u32 = 1500;
+
+    /// Splits the candles from `start_time` to `end_time` into requests of
+    /// at most `MAX_CANDLES` each, oldest first. The start is aligned to the
+    /// resolution, as the candles are.
+    pub(crate) fn chunks(
+        market_name: &str,
+        resolution: Resolution,
+        start_time: DateTime<Utc>,
+        end_time: DateTime<Utc>,
+    ) -> Vec<Self> {
+        let step = i64::from(resolution.seconds());
+        let mut start = start_time.timestamp() - start_time.timestamp().rem_euclid(step);
+        let end = end_time.timestamp();
+        let mut chunks = Vec::new();
+        while start <= end {
+            let chunk_end = (start + step * (i64::from(Self::MAX_CANDLES) - 1)).min(end);
+            chunks.push(Self {
+                market_name: market_name.to_string(),
+                resolution,
+                limit: Some(Self::MAX_CANDLES),
+                start_time: Some(Utc.timestamp_opt(start, 0).unwrap()),
+                end_time: Some(Utc.timestamp_opt(chunk_end, 0).unwrap()),
+            });
+            start = chunk_end + step;
+        }
+        chunks
+    }
+}
+
+impl Request for GetHistoricalPrices {
+    const METHOD: Method = Method::GET;
+    type Response = Prices;
+
+    fn path(&self) -> String {
+        format!("/markets/{}/candles", encode_market(&self.market_name))
+    }
+}
+
+// REST API -> Futures
+
+#[derive(Clone, Debug, Serialize)]
+pub struct GetFutures;
+
+impl Request for GetFutures {
+    const METHOD: Method = Method::GET;
+    type Response = Futures;
+
+    fn path(&self) -> String {
+        "/futures".to_string()
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct GetFuture {
+    #[serde(skip)]
+    pub future_name: String,
+}
+
+impl Request for GetFuture {
+    const METHOD: Method = Method::GET;
+    type Response = Future;
+
+    fn path(&self) -> String {
+        format!("/futures/{}", encode_segment(&self.future_name))
+    }
+}
+
+#[serde_as]
+#[derive(Clone, Debug, Serialize)]
+pub struct GetFundingPayments {
+    pub future: Option<String>,
+    pub limit: Option<u32>,
+    #[serde_as(as = "Option<TimestampSeconds<i64>>")]
+    pub start_time: Option<DateTime<Utc>>,
+    #[serde_as(as = "Option<TimestampSeconds<i64>>")]
+    pub end_time: Option<DateTime<Utc>>,
+}
+
+impl Request for GetFundingPayments {
+    const METHOD: Method = Method::GET;
+    type Response = Vec<FundingPayment>;
+
+    fn path(&self) -> String {
+        "/funding_payments".to_string()
+    }
+}
+
+// REST API -> Account
+
+#[derive(Clone, Debug, Serialize)]
+pub struct GetAccount;
+
+impl Request for GetAccount {
+    const METHOD: Method = Method::GET;
+    type Response = Account;
+
+    fn path(&self) -> String {
+        "/account".to_string()
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct GetPositions;
+
+impl Request for GetPositions {
+    const METHOD: Method = Method::GET;
+    type Response = Positions;
+
+    fn path(&self) -> String {
+        "/positions".to_string()
+    }
+}
+
+// REST API -> Wallet
+
+#[derive(Clone, Debug, Serialize)]
+pub struct GetCoins;
+
+impl Request for GetCoins {
+    const METHOD: Method = Method::GET;
+    type Response = Vec<CoinInfo>;
+
+    fn path(&self) -> String {
+        "/wallet/coins".to_string()
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct GetWalletDepositAddress {
+    #[serde(skip)]
+    pub coin: Coin,
+    pub method: Option<String>,
+}
+
+impl Request for GetWalletDepositAddress {
+    const METHOD: Method = Method::GET;
+    type Response = WalletDepositAddress;
+
+    fn path(&self) -> String {
+        format!("/wallet/deposit_address/{}", encode_segment(&self.coin))
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct GetWalletBalances;
+
+impl Request for GetWalletBalances {
+    const METHOD: Method = Method::GET;
+    type Response = Vec<WalletBalance>;
+
+    fn path(&self) -> String {
+        "/wallet/balances".to_string()
+    }
+}
+
+#[serde_as]
+#[derive(Clone, Debug, Serialize)]
+pub struct GetWalletDeposits {
+    pub limit: Option<usize>,
+    #[serde_as(as = "Option<TimestampSeconds<i64>>")]
+    pub start_time: Option<DateTime<Utc>>,
+    #[serde_as(as = "Option<TimestampSeconds<i64>>")]
+    pub end_time: Option<DateTime<Utc>>,
+}
+
+impl Request for GetWalletDeposits {
+    const METHOD: Method = Method::GET;
+    type Response = Vec<WalletDeposit>;
+
+    fn path(&self) -> String {
+        "/wallet/deposits".to_string()
+    }
+}
+
+// REST API -> Orders
+
+#[derive(Clone, Debug, Serialize)]
+pub struct GetOpenOrders {
+    pub market: Option<String>,
+}
+
+impl Request for GetOpenOrders {
+    const METHOD: Method = Method::GET;
+    type Response = Vec<OrderInfo>;
+
+    fn path(&self) -> String {
+        "/orders".to_string()
+    }
+}
+
+#[serde_as]
+#[derive(Clone, Debug, Serialize)]
+pub struct GetOrderHistory {
+    pub market: Option<String>,
+    pub limit: Option<usize>,
+    #[serde_as(as = "Option<TimestampSeconds<i64>>")]
+    pub start_time: Option<DateTime<Utc>>,
+    #[serde_as(as = "Option<TimestampSeconds<i64>>")]
+    pub end_time: Option<DateTime<Utc>>,
+}
+
+impl Request for GetOrderHistory {
+    const METHOD: Method = Method::GET;
+    type Response = Vec<OrderInfo>;
+
+    fn path(&self) -> String {
+        "/orders/history".to_string()
+    }
+}
+
+#[serde_as]
+#[derive(Clone, Debug, Serialize)]
+pub struct GetConditionalOrderHistory {
+    pub market: Option<String>,
+    pub limit: Option<usize>,
+    #[serde_as(as = "Option<TimestampSeconds<i64>>")]
+    pub start_time: Option<DateTime<Utc>>,
+    #[serde_as(as = "Option<TimestampSeconds<i64>>")]
+    pub end_time: Option<DateTime<Utc>>,
+}
+
+impl Request for GetConditionalOrderHistory {
+    const METHOD: Method = Method::GET;
+    type Response = Vec<ConditionalOrderInfo>;
+
+    fn path(&self) -> String {
+        "/conditional_orders/history".to_string()
+    }
+}
+
+/// An order to place with `Rest::place_order`.
+///
+/// Orders are built from `OrderRequest::limit` or `OrderRequest::market`,
+/// which only allow a price where FTX expects one:
+///
+/// ```
+/// use ftx::rest::{OrderRequest, Side};
+/// use rust_decimal_macros::dec;
+///
+/// let order = OrderRequest::limit("BTC-PERP", Side::Buy, dec!(40000), dec!(0.01))
+///     .post_only(true)
+///     .client_id("bid-1");
+/// assert!(order.validate().is_ok());
+/// ```
+#[serde_as]
+#[derive(Clone, Debug, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct OrderRequest {
+    market: String,
+    side: Side,
+    /// `None` for market orders, as per docs.
+    pub(super) price: Option<Decimal>,
+    pub(super) r#type: OrderType,
+    pub(super) size: Decimal,
+    reduce_only: bool,
+    ioc: bool,
+    pub(super) post_only: bool,
+    client_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    reject_on_price_band: Option<bool>,
+    #[serde_as(as = "Option<TimestampSeconds<i64>>")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    reject_after_ts: Option<DateTime<Utc>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    external_referral_program: Option<String>,
+}
+
+impl OrderRequest {
+    /// An order of the given type. Prefer `limit` or `market`, which cannot
+    /// be given a missing or superfluous price.
+    pub fn new(
+        market: impl Into<String>,
+        side: Side,
+        r#type: OrderType,
+        price: Option<Decimal>,
+        size: Decimal,
+    ) -> Self {
+        Self {
+            market: market.into(),
+            side,
+            price,
+            r#type,
+            size,
+            reduce_only: false,
+            ioc: false,
+            post_only: false,
+            client_id: None,
+            reject_on_price_band: None,
+            reject_after_ts: None,
+            external_referral_program: None,
+        }
+    }
+
+    pub fn limit(market: impl Into<String>, side: Side, price: Decimal, size: Decimal) -> Self {
+        Self::new(market, side, OrderType::Limit, Some(price), size)
+    }
+
+    pub fn market(market: impl Into<String>, side: Side, size: Decimal) -> Self {
+        Self::new(market, side, OrderType::Market, None, size)
+    }
+
+    /// Only reduce an existing position, never open or increase one.
+    pub fn reduce_only(mut self, reduce_only: bool) -> Self {
+        self.reduce_only = reduce_only;
+        self
+    }
+
+    /// Immediate-or-cancel: cancel whatever does not fill right away.
+    pub fn ioc(mut self, ioc: bool) -> Self {
+        self.ioc = ioc;
+        self
+    }
+
+    /// Only provide liquidity. Orders that would take are rejected.
+    pub fn post_only(mut self, post_only: bool) -> Self {
+        self.post_only = post_only;
+        self
+    }
+
+    pub fn client_id(mut self, client_id: impl Into<String>) -> Self {
+        self.client_id = Some(client_id.into());
+        self
+    }
+
+    /// Reject the order if its price is outside the price band, instead of
+    /// moving the price to the edge of the band.
+    pub fn reject_on_price_band(mut self, reject_on_price_band: bool) -> Self {
+        self.reject_on_price_band = Some(reject_on_price_band);
+        self
+    }
+
+    /// Reject the order if it would be processed after the given time, e.g.
+    /// when the exchange is under heavy load.
+    pub fn reject_after_ts(mut self, reject_after_ts: DateTime<Utc>) -> Self {
+        self.reject_after_ts = Some(reject_after_ts);
+        self
+    }
+
+    pub fn external_referral_program(mut self, program: impl Into<String>) -> Self {
+        self.external_referral_program = Some(program.into());
+        self
+    }
+
+    /// Checks the rules FTX applies to combinations of parameters, so that
+    /// invalid orders fail before being sent.
+    pub fn validate(&self) -> Result<()> {
+        match (self.r#type, self.price) {
+            (OrderType::Limit, None) => return Err(Error::PlacingLimitOrderRequiresPrice),
+            (OrderType::Limit, Some(price)) if price <= Decimal::zero() => {
+                return Err(Error::InvalidOrder("price must be positive"))
+            }
+            (OrderType::Market, Some(_)) => {
+                return Err(Error::InvalidOrder("market orders cannot have a price"))
+            }
+            _ => {}
+        }
+        if self.size <= Decimal::zero() {
+            return Err(Error::InvalidOrder("size must be positive"));
+        }
+        if self.post_only && self.r#type == OrderType::Market {
+            return Err(Error::InvalidOrder("market orders cannot be post-only"));
+        }
+        if self.post_only && self.ioc {
+            return Err(Error::InvalidOrder(
+                "orders cannot be both post-only and immediate-or-cancel",
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl OrderRequest {
+    /// Rounds the price and size to the grid of the market, see
+    /// `Market::round_price` and `Market::round_size`.
+    pub fn round_to(mut self, market: &Market) -> Self {
+        self.price = self.price.map(|price| market.round_price(price, self.side));
+        self.size = market.round_size(self.size);
+        self
+    }
+
+    /// Like `validate`, but also checks the order against the metadata of
+    /// its market, which catches orders the exchange would reject for their
+    /// increments or minimum size without sending them.
+    pub fn validate_for(&self, market: &Market) -> Result<()> {
+        self.validate()?;
+        if market.name != self.market {
+            return Err(Error::InvalidOrder("order is for a different market"));
+        }
+        if !market.enabled {
+            return Err(Error::InvalidOrder("market is disabled"));
+        }
+        if market.post_only && !self.post_only {
+            return Err(Error::InvalidOrder("market only accepts post-only orders"));
+        }
+        if let Some(price) = self.price {
+            if !is_multiple(price, market.price_increment) {
+                return Err(Error::InvalidOrder(
+                    "price is not a multiple of the price increment",
+                ));
+            }
+        }
+        if !is_multiple(self.size, market.size_increment) {
+            return Err(Error::InvalidOrder(
+                "size is not a multiple of the size increment",
+            ));
+        }
+        // Any limit order that is not immediate-or-cancel can rest on the
+        // book, so it is held to the minimum size for providing liquidity
+        let can_rest = self.r#type == OrderType::Limit && !self.ioc;
+        if can_rest && self.size < market.min_provide_size {
+            return Err(Error::InvalidOrder(
+                "size is below the minimum size for providing liquidity",
+            ));
+        }
+        Ok(())
+    }
+}
+
+fn is_multiple(value: Decimal, increment: Decimal) -> bool {
+    increment.is_zero() || (value % increment).is_zero()
+}
+
+impl Request for OrderRequest {
+    const METHOD: Method = Method::POST;
+    type Response = OrderInfo;
+
+    fn path(&self) -> String {
+        "/orders".to_string()
+    }
+}
+
+#[derive(Copy, Clone, Debug, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum PlaceConditionalOrderType {
+    Stop {
+        trigger_price: Decimal,
+        order_price: Option<Decimal>,
+    },
+    TrailingStop {
+        trail_value: Decimal,
+    },
+    TakeProfit {
+        trigger_price: Decimal,
+        order_price: Option<Decimal>,
+    },
+}
+
+#[derive(Debug, Clone)]
+pub struct PlaceConditionalOrder {
+    pub market: String,
+    pub side: Side,
+    pub size: Decimal,
+    pub r#type: PlaceConditionalOrderType,
+    pub reduce_only: bool,
+    pub retry_until_filled: bool,
+}
+
+impl Serialize for PlaceConditionalOrder {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        #[derive(Serialize)]
+        #[serde(rename_all = "camelCase")]
+        struct Body<'a> {
+            market: &'a str,
+            side: Side,
+            size: Decimal,
+            r#type: &'static str,
+            reduce_only: bool,
+            retry_until_filled: bool,
+            trigger_price: Option<Decimal>,
+            order_price: Option<Decimal>,
+            trail_value: Option<Decimal>,
+        }
+
+        let (r#type, trigger_price, order_price, trail_value) = match self.r#type {
+            PlaceConditionalOrderType::Stop {
+                trigger_price,
+                order_price,
+            } => ("stop", Some(trigger_price), order_price, None),
+            PlaceConditionalOrderType::TrailingStop { trail_value } => {
+                ("trailingStop", None, None, Some(trail_value))
+            }
+            PlaceConditionalOrderType::TakeProfit {
+                trigger_price,
+                order_price,
+            } => ("takeProfit", Some(trigger_price), order_price, None),
+        };
+
+        Body {
+            market: &self.market,
+            side: self.side,
+            size: self.size,
+            r#type,
+            reduce_only: self.reduce_only,
+            retry_until_filled: self.retry_until_filled,
+            trigger_price,
+            order_price,
+            trail_value,
+        }
+        .serialize(serializer)
+    }
+}
+
+impl Request for PlaceConditionalOrder {
+    const METHOD: Method = Method::POST;
+    type Response = ConditionalOrderInfo;
+
+    fn path(&self) -> String {
+        "/conditional_orders".to_string()
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ModifyOrder {
+    #[serde(skip)]
+    pub order_id: Id,
+    pub price: Option<Decimal>,
+    pub size: Option<Decimal>,
+    pub client_id: Option<String>,
+}
+
+impl Request for ModifyOrder {
+    const METHOD: Method = Method::POST;
+    type Response = OrderInfo;
+
+    fn path(&self) -> String {
+        format!("/orders/{}/modify", self.order_id)
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ModifyOrderByClientId {
+    pub client_id: String,
+    pub price: Option<Decimal>,
+    pub size: Option<Decimal>,
+}
+
+impl Request for ModifyOrderByClientId {
+    const METHOD: Method = Method::POST;
+    type Response = OrderInfo;
+
+    fn path(&self) -> String {
+        format!(
+            "/orders/by_client_id/{}/modify",
+            encode_segment(&self.client_id)
+        )
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct GetOrder {
+    #[serde(skip)]
+    pub order_id: Id,
+}
+
+impl Request for GetOrder {
+    const METHOD: Method = Method::GET;
+    type Response = OrderInfo;
+
+    fn path(&self) -> String {
+        format!("/orders/{}", self.order_id)
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct GetOrderByClientId {
+    #[serde(skip)]
+    pub client_id: String,
+}
+
+impl Request for GetOrderByClientId {
+    const METHOD: Method = Method::GET;
+    type Response = OrderInfo;
+
+    fn path(&self) -> String {
+        format!("/orders/by_client_id/{}", encode_segment(&self.client_id))
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CancelAllOrders {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub market: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub side: Option<Side>,
+    pub conditional_orders_only: bool,
+    pub limit_orders_only: bool,
+}
+
+impl Request for CancelAllOrders {
+    const METHOD: Method = Method::DELETE;
+    type Response = String;
+
+    fn path(&self) -> String {
+        "/orders".to_string()
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct CancelOrder {
+    #[serde(skip)]
+    pub order_id: Id,
+}
+
+impl Request for CancelOrder {
+    const METHOD: Method = Method::DELETE;
+    type Response = String;
+
+    fn path(&self) -> String {
+        format!("/orders/{}", self.order_id)
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct CancelOrderByClientId {
+    #[serde(skip)]
+    pub client_id: String,
+}
+
+impl Request for CancelOrderByClientId {
+    const METHOD: Method = Method::DELETE;
+    type Response = String;
+
+    fn path(&self) -> String {
+        format!("/orders/by_client_id/{}", encode_segment(&self.client_id))
+    }
+}
+
+// REST API -> Fills
+
+#[serde_as]
+#[derive(Clone, Debug, Serialize)]
+pub struct GetFills {
+    pub market: Option<String>,
+    pub limit: Option<usize>,
+    #[serde(rename = "orderId")]
+    pub order_id: Option<Id>,
+    #[serde_as(as = "Option<TimestampSeconds<i64>>")]
+    pub start_time: Option<DateTime<Utc>>,
+    #[serde_as(as = "Option<TimestampSeconds<i64>>")]
+    pub end_time: Option<DateTime<Utc>>,
+}
+
+impl Request for GetFills {
+    const METHOD: Method = Method::GET;
+    type Response = Vec<FillInfo>;
+
+    fn path(&self) -> String {
+        "/fills".to_string()
+    }
+}
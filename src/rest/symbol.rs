@@ -0,0 +1,230 @@
+use super::{Coin, Error, Result};
+use chrono::{Datelike, NaiveDate};
+use serde_with::{DeserializeFromStr, SerializeDisplay};
+use std::fmt;
+use std::str::FromStr;
+
+/// A market name parsed into its kind, e.g. `BTC/USD`, `BTC-PERP`,
+/// `BTC-0325` or `BTC-MOVE-WK-0326`.
+///
+/// Parsing and formatting round-trip, and the type is (de)serialized as the
+/// market name, so it can be used in place of `Symbol` in your own types.
+#[derive(
+    Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, SerializeDisplay, DeserializeFromStr,
+)]
+pub enum MarketSymbol {
+    /// `BASE/QUOTE`, e.g. `BTC/USD`.
+    Spot { base: Coin, quote: Coin },
+    /// `XXX-PERP`.
+    Perpetual { underlying: Coin },
+    /// `XXX-0325`, or `XXX-20190628` for expired futures.
+    Future { underlying: Coin, expiry: Expiry },
+    /// `XXX-MOVE-0325` (daily), `XXX-MOVE-WK-0326` (weekly) or
+    /// `XXX-MOVE-2021Q1` (quarterly).
+    Move {
+        underlying: Coin,
+        weekly: bool,
+        expiry: Expiry,
+    },
+    /// The BVOL and inverse IBVOL bitcoin volatility tokens, e.g. `BVOL/USD`.
+    Bvol { inverse: bool, quote: Coin },
+    /// Prediction markets, e.g. `TRUMP2024`.
+    Prediction { name: String },
+}
+
+impl MarketSymbol {
+    /// The coin the contract is based on. `None` for spot, BVOL and
+    /// prediction markets.
+    pub fn underlying(&self) -> Option<&str> {
+        match self {
+            Self::Perpetual { underlying }
+            | Self::Future { underlying, .. }
+            | Self::Move { underlying, .. } => Some(underlying),
+            Self::Spot { .. } | Self::Bvol { .. } | Self::Prediction { .. } => None,
+        }
+    }
+
+    /// The expiry of dated futures and MOVE contracts.
+    pub fn expiry(&self) -> Option<Expiry> {
+        match self {
+            Self::Future { expiry, .. } | Self::Move { expiry, .. } => Some(*expiry),
+            _ => None,
+        }
+    }
+
+    pub fn is_spot(&self) -> bool {
+        matches!(self, Self::Spot { .. } | Self::Bvol { .. })
+    }
+}
+
+impl FromStr for MarketSymbol {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let invalid = || Error::InvalidSymbol(s.to_string());
+        let coin = |coin: &str| {
+            if !coin.is_empty() && coin.chars().all(|c| c.is_ascii_alphanumeric()) {
+                Ok(coin.to_string())
+            } else {
+                Err(invalid())
+            }
+        };
+
+        if let Some((base, quote)) = s.split_once('/') {
+            let quote = coin(quote)?;
+            return Ok(match base {
+                "BVOL" => Self::Bvol {
+                    inverse: false,
+                    quote,
+                },
+                "IBVOL" => Self::Bvol {
+                    inverse: true,
+                    quote,
+                },
+                base => Self::Spot {
+                    base: coin(base)?,
+                    quote,
+                },
+            });
+        }
+
+        let parts: Vec<&str> = s.split('-').collect();
+        let expiry = |expiry: &str| expiry.parse::<Expiry>().map_err(|_| invalid());
+        Ok(match parts[..] {
+            [name] => Self::Prediction { name: coin(name)? },
+            [underlying, "PERP"] => Self::Perpetual {
+                underlying: coin(underlying)?,
+            },
+            [underlying, "MOVE", "WK", date] => Self::Move {
+                underlying: coin(underlying)?,
+                weekly: true,
+                expiry: expiry(date)?,
+            },
+            [underlying, "MOVE", date] => Self::Move {
+                underlying: coin(underlying)?,
+                weekly: false,
+                expiry: expiry(date)?,
+            },
+            [underlying, date] => match expiry(date)? {
+                Expiry::Quarter { .. } => return Err(invalid()),
+                expiry => Self::Future {
+                    underlying: coin(underlying)?,
+                    expiry,
+                },
+            },
+            _ => return Err(invalid()),
+        })
+    }
+}
+
+impl fmt::Display for MarketSymbol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Spot { base, quote } => write!(f, "{}/{}", base, quote),
+            Self::Perpetual { underlying } => write!(f, "{}-PERP", underlying),
+            Self::Future { underlying, expiry } => write!(f, "{}-{}", underlying, expiry),
+            Self::Move {
+                underlying,
+                weekly,
+                expiry,
+            } => {
+                let week = if *weekly { "WK-" } else { "" };
+                write!(f, "{}-MOVE-{}{}", underlying, week, expiry)
+            }
+            Self::Bvol { inverse, quote } => {
+                let inverse = if *inverse { "I" } else { "" };
+                write!(f, "{}BVOL/{}", inverse, quote)
+            }
+            Self::Prediction { name } => f.write_str(name),
+        }
+    }
+}
+
+/// The expiry in the name of a dated future or MOVE contract.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Expiry {
+    /// `MMDD`, used by contracts that have not expired yet. The year is
+    /// implied.
+    MonthDay { month: u32, day: u32 },
+    /// `YYYYMMDD`, used by expired contracts.
+    Date(NaiveDate),
+    /// `YYYYQn`, used by quarterly MOVE contracts.
+    Quarter { year: i32, quarter: u32 },
+}
+
+impl Expiry {
+    /// The date of expiry. `MMDD` expiries resolve to the first such date on
+    /// or after `today`, and quarters to their last day. `None` if the
+    /// expiry is not a valid date.
+    pub fn date(&self, today: NaiveDate) -> Option<NaiveDate> {
+        match *self {
+            Self::MonthDay { month, day } => (today.year()..=today.year() + 4)
+                .filter_map(|year| NaiveDate::from_ymd_opt(year, month, day))
+                .find(|date| *date >= today),
+            Self::Date(date) => Some(date),
+            Self::Quarter { year, quarter } if (1..=4).contains(&quarter) => {
+                let (year, month) = if quarter == 4 {
+                    (year + 1, 1)
+                } else {
+                    (year, quarter * 3 + 1)
+                };
+                NaiveDate::from_ymd_opt(year, month, 1)?.pred_opt()
+            }
+            Self::Quarter { .. } => None,
+        }
+    }
+}
+
+impl FromStr for Expiry {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let invalid = || Error::InvalidSymbol(s.to_string());
+        let number = |digits: &str| {
+            if digits.chars().all(|c| c.is_ascii_digit()) {
+                digits.parse::<u32>().map_err(|_| invalid())
+            } else {
+                Err(invalid())
+            }
+        };
+
+        if !s.is_ascii() {
+            return Err(invalid());
+        }
+        match s.len() {
+            4 => {
+                let (month, day) = (number(&s[..2])?, number(&s[2..])?);
+                // Any leap year accepts every valid month and day
+                NaiveDate::from_ymd_opt(2000, month, day).ok_or_else(invalid)?;
+                Ok(Self::MonthDay { month, day })
+            }
+            6 if s.as_bytes()[4] == b'Q' => {
+                let (year, quarter) = (number(&s[..4])?, number(&s[5..])?);
+                if !(1..=4).contains(&quarter) {
+                    return Err(invalid());
+                }
+                Ok(Self::Quarter {
+                    year: year as i32,
+                    quarter,
+                })
+            }
+            8 => {
+                number(s)?;
+                NaiveDate::parse_from_str(s, "%Y%m%d")
+                    .map(Self::Date)
+                    .map_err(|_| invalid())
+            }
+            _ => Err(invalid()),
+        }
+    }
+}
+
+impl fmt::Display for Expiry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MonthDay { month, day } => write!(f, "{:02}{:02}", month, day),
+            Self::Date(date) => write!(f, "{}", date.format("%Y%m%d")),
+            Self::Quarter { year, quarter } => write!(f, "{}Q{}", year, quarter),
+        }
+    }
+}
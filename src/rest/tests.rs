@@ -1,21 +1,50 @@
 use super::*;
+use crate::credentials::Credentials;
+use chrono::{NaiveDate, TimeZone};
 use dotenv::dotenv;
 use rust_decimal_macros::dec;
+use serde_json::json;
 use std::env::var;
 
+/// Directory the tests record responses to and replay them from.
+const FIXTURES: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/rest");
+
+/// Hand-written responses in the documented FTX format, for replaying the
+/// tests where nothing was recorded. They only show that the client parses
+/// that format, not that it matches the live API.
+const SYNTHETIC_FIXTURES: &str =
+    concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/synthetic/rest");
+
+/// Reads `FTX_FIXTURES`, which can be set to `record`, `replay` or
+/// `synthetic`, and returns the mode and directory of the fixtures.
+fn fixture_mode() -> Option<(FixtureMode, &'static str)> {
+    match var("FTX_FIXTURES").ok()?.as_str() {
+        "record" => Some((FixtureMode::Record, FIXTURES)),
+        "replay" => Some((FixtureMode::Replay, FIXTURES)),
+        "synthetic" => Some((FixtureMode::Replay, SYNTHETIC_FIXTURES)),
+        mode => panic!("Unknown fixture mode {:?}.", mode),
+    }
+}
+
 async fn init_api() -> Rest {
     dotenv().ok();
 
-    let subaccount = var("SUBACCOUNT").ok();
-
-    let proxy = var("SOCKS_PROXY").ok();
-
-    let api = Rest::new(
-        var("FTX_API_KEY").expect("API Key is not defined."),
-        var("FTX_API_SECRET").expect("API Secret is not defined."),
-        subaccount.clone(),
-        proxy,
-    );
+    let subaccount = var("FTX_SUBACCOUNT").ok();
+
+    let fixtures = fixture_mode();
+    let api = if matches!(fixtures, Some((FixtureMode::Replay, _))) {
+        // Replayed responses do not depend on the credentials
+        Rest::new(String::new(), String::new(), subaccount.clone(), None).unwrap()
+    } else {
+        Credentials::from_env()
+            .expect("API credentials are not defined.")
+            .rest()
+            .unwrap()
+    };
+    let api = match fixtures {
+        Some((mode, dir)) => api.with_fixtures(dir, mode),
+        None => api,
+    };
 
     // Test create subaccount only if credentials are account-wide
     if subaccount.is_none() {
@@ -35,7 +64,7 @@ fn read_only<T>(result: Result<T>) {
 #[tokio::test]
 async fn get_subaccounts() {
     let rest = init_api().await;
-    if rest.subaccount.is_none() {
+    if rest.subaccount().is_none() {
         // Test only if credentials are account-wide
         rest.get_subaccounts().await.unwrap();
     }
@@ -44,7 +73,7 @@ async fn get_subaccounts() {
 #[tokio::test]
 async fn create_subaccount() {
     let rest = init_api().await;
-    if rest.subaccount.is_none() {
+    if rest.subaccount().is_none() {
         // Test only if credentials are account-wide
         read_only(rest.create_subaccount("Bot").await);
     }
@@ -53,7 +82,7 @@ async fn create_subaccount() {
 #[tokio::test]
 async fn change_subaccount_name() {
     let rest = init_api().await;
-    if rest.subaccount.is_none() {
+    if rest.subaccount().is_none() {
         // Test only if credentials are account-wide
         read_only(rest.change_subaccount_name("Bot", "Bot").await);
     }
@@ -62,7 +91,7 @@ async fn change_subaccount_name() {
 #[tokio::test]
 async fn delete_subaccount() {
     let rest = init_api().await;
-    if rest.subaccount.is_none() {
+    if rest.subaccount().is_none() {
         // Test only if credentials are account-wide
         read_only(rest.delete_subaccount("Bot").await);
     }
@@ -72,7 +101,7 @@ async fn delete_subaccount() {
 async fn get_subaccount_balances() {
     let rest = init_api().await;
     // Test using given subaccount otherwise use "Bot"
-    let subaccount = match &rest.subaccount {
+    let subaccount = match rest.subaccount() {
         None => "Bot",
         Some(sub) => sub,
     };
@@ -82,7 +111,7 @@ async fn get_subaccount_balances() {
 #[tokio::test]
 async fn transfer_between_subaccounts() {
     let rest = init_api().await;
-    if rest.subaccount.is_none() {
+    if rest.subaccount().is_none() {
         // Test only if credentials are account-wide
         rest.transfer_between_subaccounts("BTC", Decimal::zero(), "Source", "Destination")
             .await
@@ -127,11 +156,78 @@ async fn get_trades() {
 async fn get_historical_prices() {
     init_api()
         .await
-        .get_historical_prices("BTC/USD", 300, None, None, None)
+        .get_historical_prices("BTC/USD", Resolution::FiveMinutes, None, None, None)
         .await
         .unwrap();
 }
 
+#[tokio::test]
+async fn get_historical_prices_range() {
+    // Fixed times, so that the requests match the fixtures
+    let start_time = Utc.with_ymd_and_hms(2021, 11, 1, 0, 0, 0).unwrap();
+    let end_time = start_time + chrono::Duration::minutes(2000);
+    let prices = init_api()
+        .await
+        .get_historical_prices_range("BTC/USD", Resolution::OneMinute, start_time, end_time)
+        .await
+        .unwrap();
+    // Two requests are needed for 2001 candles
+    assert_eq!(start_time, prices.first().unwrap().start_time);
+    assert_eq!(end_time, prices.last().unwrap().start_time);
+    assert!(prices
+        .windows(2)
+        .all(|pair| pair[1].start_time - pair[0].start_time >= chrono::Duration::minutes(1)));
+}
+
+#[test]
+fn resolutions() {
+    use std::convert::TryFrom;
+
+    for seconds in [15, 60, 300, 900, 3600, 14400, 86400, 30 * 86400] {
+        let resolution = Resolution::try_from(seconds).unwrap();
+        assert_eq!(seconds, resolution.seconds());
+        assert_eq!(resolution, serde_json::from_value(json!(seconds)).unwrap());
+        assert_eq!(json!(seconds), serde_json::to_value(resolution).unwrap());
+    }
+    for seconds in [0, 30, 86401, 31 * 86400] {
+        assert!(Resolution::try_from(seconds).is_err());
+    }
+    assert_eq!(
+        Resolution::FourHours,
+        Resolution::try_from(chrono::Duration::hours(4)).unwrap()
+    );
+    assert!(Resolution::try_from(chrono::Duration::milliseconds(15500)).is_err());
+    assert_eq!(
+        chrono::Duration::days(2),
+        Resolution::days(2).unwrap().into()
+    );
+    assert!(Resolution::days(0).is_err());
+    assert!(Resolution::days(31).is_err());
+    assert!(Resolution::days(u32::MAX).is_err());
+}
+
+#[test]
+fn historical_prices_chunks() {
+    let time = |secs| Utc.timestamp_opt(secs, 0).unwrap();
+    let year = 365 * 86400;
+    let chunks =
+        GetHistoricalPrices::chunks("BTC/USD", Resolution::OneMinute, time(30), time(year));
+    assert_eq!(351, chunks.len());
+    assert_eq!(Some(time(0)), chunks[0].start_time);
+    for pair in chunks.windows(2) {
+        let end = pair[0].end_time.unwrap();
+        assert_eq!(
+            GetHistoricalPrices::MAX_CANDLES as i64 - 1,
+            (end - pair[0].start_time.unwrap()).num_minutes()
+        );
+        assert_eq!(Some(end + chrono::Duration::minutes(1)), pair[1].start_time);
+    }
+    assert_eq!(Some(time(year)), chunks.last().unwrap().end_time);
+
+    let chunks = GetHistoricalPrices::chunks("BTC/USD", Resolution::ONE_DAY, time(0), time(0));
+    assert_eq!(1, chunks.len());
+}
+
 #[tokio::test]
 async fn get_futures() {
     init_api().await.get_futures().await.unwrap();
@@ -184,7 +280,438 @@ async fn account_deserialization() {
         "useFttCollateral":true,
         "username":"foo@example.com"
     }"#;
-    let _account: Account = serde_json::from_str(json).unwrap();
+    let account: Account = round_trip(json);
+    assert_eq!(Side::Sell, account.positions[0].side);
+}
+
+/// Deserializes `json`, serializes the result and deserializes that again,
+/// checking that the value survives the round trip.
+pub fn round_trip<T>(json: &str) -> T
+where
+    T: serde::Serialize + DeserializeOwned + PartialEq + std::fmt::Debug,
+{
+    let value: T = serde_json::from_str(json).unwrap();
+    let serialized = serde_json::to_string(&value).unwrap();
+    assert_eq!(value, serde_json::from_str::<T>(&serialized).unwrap());
+    value
+}
+
+#[test]
+fn model_round_trip() {
+    round_trip::<Market>(
+        r#"{
+            "name":"BTC-PERP","type":"future","underlying":"BTC","baseCurrency":null,
+            "quoteCurrency":null,"enabled":true,"ask":40000.5,"bid":40000,"last":40000.5,
+            "postOnly":false,"priceIncrement":0.5,"sizeIncrement":0.0001,"restricted":false,
+            "minProvideSize":0.0001,"price":40000.5,"highLeverageFeeExempt":true,
+            "change1h":0.001,"change24h":-0.02,"changeBod":0.01,
+            "quoteVolume24h":1234567.8,"volumeUsd24h":1234567.8
+        }"#,
+    );
+    round_trip::<Future>(
+        r#"{
+            "ask":40000.5,"bid":40000,"change1h":0.001,"change24h":-0.02,"changeBod":0.01,
+            "volumeUsd24h":1234567.8,"volume":30.5,"description":"Bitcoin Perpetual Futures",
+            "enabled":true,"expired":false,"expiry":null,"index":40001.2,"imfFactor":0.002,
+            "last":40000.5,"lowerBound":38000,"mark":40000.5,"name":"BTC-PERP",
+            "perpetual":true,"positionLimitWeight":1,"postOnly":false,"priceIncrement":0.5,
+            "sizeIncrement":0.0001,"underlying":"BTC","upperBound":42000,"type":"perpetual"
+        }"#,
+    );
+    round_trip::<FutureStats>(
+        r#"{
+            "volume":1000.23,"nextFundingRate":0.00025,
+            "nextFundingTime":"2019-03-29T03:00:00+00:00","expirationPrice":3992.1,
+            "predictedExpirationPrice":3993.6,"strikePrice":8182.35,"openInterest":21124.583
+        }"#,
+    );
+    round_trip::<Trades>(
+        r#"[{
+            "id":3855995,"liquidation":false,"price":3857.75,"side":"buy","size":0.111,
+            "time":"2019-03-20T18:16:23.397991+00:00"
+        }]"#,
+    );
+    round_trip::<Prices>(
+        r#"[{
+            "close":11055.25,"high":11089,"low":11043.5,"open":11059.25,"volume":464193.95,
+            "startTime":"2019-06-24T17:15:00+00:00"
+        }]"#,
+    );
+    round_trip::<WalletDeposit>(
+        r#"{
+            "id":1,"coin":"TUSD","size":99,"time":"2019-03-05T09:56:55.728933+00:00",
+            "status":"confirmed","confirmations":12,
+            "confirmedTime":"2019-03-05T09:56:55.728933+00:00","fee":0,
+            "txid":"0x8078356ae4b06a036d64747546c274af19581f1c78c510b60505798a7ffcaf1",
+            "notes":null
+        }"#,
+    );
+    round_trip::<OrderInfo>(
+        r#"{
+            "id":9596912,"market":"XRP-PERP","future":"XRP-PERP","type":"limit",
+            "side":"sell","price":0.306525,"size":31431,"reduceOnly":false,"ioc":false,
+            "postOnly":false,"status":"open","filledSize":0,"remainingSize":31431,
+            "avgFillPrice":null,"liquidation":null,
+            "createdAt":"2019-03-05T09:56:55.728933+00:00","clientId":null
+        }"#,
+    );
+    round_trip::<ConditionalOrderInfo>(
+        r#"{
+            "id":50001,"market":"XRP-PERP","future":"XRP-PERP","orderType":"market",
+            "type":"trailing_stop","side":"buy","price":null,"size":0.003,
+            "status":"open","trailStart":0.3,"trailValue":-0.01,"triggerPrice":0.306,
+            "triggeredAt":null,"filledSize":0,"avgFillPrice":null,
+            "createdAt":"2019-03-16T22:11:30.133476+00:00","clientId":null,
+            "orderPrice":null,"retryUntilFilled":true
+        }"#,
+    );
+    round_trip::<FillInfo>(
+        r#"{
+            "id":11,"market":"BTC-PERP","future":"BTC-PERP","type":"order","side":"buy",
+            "price":10050.5,"size":0.001,"time":"2019-03-05T09:56:55.728933+00:00",
+            "fee":0.0000201,"feeCurrency":"USD","feeRate":0.0002,"liquidity":"taker",
+            "baseCurrency":null,"quoteCurrency":null,"orderId":103,"tradeId":1034
+        }"#,
+    );
+    round_trip::<SuccessResponse<Subaccounts>>(
+        r#"{
+            "success":true,
+            "result":[{"nickname":"sub1","deletable":true,"editable":true,"competition":false}]
+        }"#,
+    );
+}
+
+#[test]
+fn unexpected_response() {
+    let page = format!(
+        "<html><head><title>502 Bad Gateway</title></head><body>{}</body></html>",
+        "cloudflare ".repeat(100)
+    );
+    match parse_response::<Markets>(StatusCode::BAD_GATEWAY, page.as_bytes()) {
+        Err(Error::UnexpectedResponse { status, body, .. }) => {
+            assert_eq!(StatusCode::BAD_GATEWAY, status);
+            assert!(body.starts_with("<html><head><title>502 Bad Gateway"));
+            assert!(body.len() < page.len());
+        }
+        _ => panic!("Unexpected response error expected."),
+    }
+
+    match parse_response::<Markets>(
+        StatusCode::BAD_REQUEST,
+        br#"{"success":false,"error":"No such market: FOO/BAR"}"#,
+    ) {
+        Err(Error::Api(error)) => assert_eq!("No such market: FOO/BAR", error),
+        _ => panic!("Api error expected."),
+    }
+}
+
+#[test]
+fn request_encoding() {
+    assert_eq!(
+        "/orders/by_client_id/a%2Fb%20c%3F%23",
+        GetOrderByClientId {
+            client_id: "a/b c?#".to_string(),
+        }
+        .path()
+    );
+    assert_eq!(
+        "/markets/BTC/USD/orderbook",
+        GetOrderbook {
+            market_name: "BTC/USD".to_string(),
+            depth: None,
+        }
+        .path()
+    );
+
+    let fills = GetFills {
+        market: Some("BTC/USD".to_string()),
+        limit: None,
+        order_id: Some(42),
+        start_time: Utc.timestamp_opt(1_600_000_000, 0).single(),
+        end_time: None,
+    };
+    assert_eq!(
+        json!({
+            "market": "BTC/USD",
+            "limit": null,
+            "orderId": 42,
+            "start_time": 1_600_000_000,
+            "end_time": null,
+        }),
+        serde_json::to_value(&fills).unwrap()
+    );
+
+    let order = PlaceConditionalOrder {
+        market: "BTC-PERP".to_string(),
+        side: Side::Sell,
+        size: dec!(1),
+        r#type: PlaceConditionalOrderType::TrailingStop {
+            trail_value: dec!(-50),
+        },
+        reduce_only: true,
+        retry_until_filled: false,
+    };
+    let order = serde_json::to_value(&order).unwrap();
+    assert_eq!("trailingStop", order["type"]);
+    assert_eq!(json!(dec!(-50)), order["trailValue"]);
+    assert_eq!(Value::Null, order["triggerPrice"]);
+}
+
+#[test]
+fn unknown_variants() {
+    let market: Market = round_trip(
+        r#"{
+            "name":"BTC-1231-50000-C","type":"option","underlying":"BTC","baseCurrency":null,
+            "quoteCurrency":null,"enabled":true,"ask":1200,"bid":1100,"last":1150,
+            "postOnly":false,"priceIncrement":1,"sizeIncrement":0.01,"restricted":false,
+            "minProvideSize":0.01,"price":1150,"highLeverageFeeExempt":false,
+            "change1h":0,"change24h":0,"changeBod":0,"quoteVolume24h":0,"volumeUsd24h":0
+        }"#,
+    );
+    assert_eq!(
+        MarketType::Unknown("option".to_string()),
+        market.market_type
+    );
+    assert_eq!(
+        MarketType::Spot,
+        serde_json::from_str::<MarketType>(r#""spot""#).unwrap()
+    );
+    assert_eq!(
+        OrderStatus::Unknown("triggered".to_string()),
+        serde_json::from_str(r#""triggered""#).unwrap()
+    );
+    assert_eq!(
+        r#""pending""#,
+        serde_json::to_string(&DepositStatus::Unknown("pending".to_string())).unwrap()
+    );
+    assert_eq!(
+        FutureType::Unknown("option".to_string()),
+        serde_json::from_str(r#""option""#).unwrap()
+    );
+    // Only strings are accepted
+    assert!(serde_json::from_str::<OrderStatus>("1").is_err());
+
+    // Each unknown value is only logged once per type
+    assert!(first_unknown("tests::Unknown", "a"));
+    assert!(!first_unknown("tests::Unknown", "a"));
+    assert!(first_unknown("tests::Unknown", "b"));
+    assert!(first_unknown("tests::Other", "a"));
+}
+
+#[test]
+fn market_symbols() {
+    for name in [
+        "BTC/USD",
+        "1INCH-PERP",
+        "BTC-0325",
+        "BTC-20190628",
+        "BTC-MOVE-0325",
+        "BTC-MOVE-WK-0326",
+        "BTC-MOVE-2021Q1",
+        "BTC-MOVE-20210325",
+        "BVOL/USD",
+        "IBVOL/USDT",
+        "TRUMP2024",
+    ] {
+        let symbol: MarketSymbol = name.parse().unwrap();
+        assert_eq!(name, symbol.to_string());
+        let json = serde_json::to_string(&symbol).unwrap();
+        assert_eq!(format!("{:?}", name), json);
+        assert_eq!(symbol, serde_json::from_str(&json).unwrap());
+    }
+
+    let symbol: MarketSymbol = "ETH-MOVE-WK-0326".parse().unwrap();
+    assert_eq!(
+        MarketSymbol::Move {
+            underlying: "ETH".to_string(),
+            weekly: true,
+            expiry: Expiry::MonthDay { month: 3, day: 26 },
+        },
+        symbol
+    );
+    assert_eq!(Some("ETH"), symbol.underlying());
+    assert!(!symbol.is_spot());
+    let date = NaiveDate::from_ymd_opt;
+    let today = date(2021, 6, 1).unwrap();
+    let expiry = symbol.expiry().unwrap();
+    assert_eq!(date(2022, 3, 26), expiry.date(today));
+    assert_eq!(
+        date(2021, 12, 31),
+        Expiry::Quarter {
+            year: 2021,
+            quarter: 4
+        }
+        .date(today)
+    );
+    assert_eq!(
+        date(2024, 2, 29),
+        Expiry::MonthDay { month: 2, day: 29 }.date(today)
+    );
+
+    let symbol: MarketSymbol = "BTC/USD".parse().unwrap();
+    assert!(symbol.is_spot());
+    assert_eq!((None, None), (symbol.underlying(), symbol.expiry()));
+
+    for name in [
+        "",
+        "BTC/",
+        "BTC-",
+        "BTC-PERP-0325",
+        "BTC-1332",
+        "BTC-2021Q1",
+        "BTC-MOVE-2021Q5",
+        "BTC-HASH-2021Q1",
+        "BTC-MOVE-WK-03é",
+    ] {
+        match name.parse::<MarketSymbol>() {
+            Err(Error::InvalidSymbol(invalid)) => assert_eq!(name, invalid),
+            result => panic!("{:?} should be invalid, got {:?}", name, result),
+        }
+    }
+    assert!(serde_json::from_str::<MarketSymbol>("\"BTC-1332\"").is_err());
+}
+
+#[test]
+fn order_request() {
+    let order = OrderRequest::limit("BTC-PERP", Side::Buy, dec!(40000), dec!(0.01))
+        .post_only(true)
+        .client_id("bid-1")
+        .reject_after_ts(Utc.timestamp_opt(1_600_000_000, 0).single().unwrap());
+    order.validate().unwrap();
+    assert_eq!(
+        json!({
+            "market": "BTC-PERP",
+            "side": "buy",
+            "price": dec!(40000),
+            "type": "limit",
+            "size": dec!(0.01),
+            "reduceOnly": false,
+            "ioc": false,
+            "postOnly": true,
+            "clientId": "bid-1",
+            "rejectAfterTs": 1_600_000_000,
+        }),
+        serde_json::to_value(&order).unwrap()
+    );
+
+    let order = OrderRequest::market("BTC-PERP", Side::Sell, dec!(0.01))
+        .reject_on_price_band(true)
+        .external_referral_program("FTX");
+    order.validate().unwrap();
+    let order = serde_json::to_value(&order).unwrap();
+    assert_eq!(Value::Null, order["price"]);
+    assert_eq!(json!(true), order["rejectOnPriceBand"]);
+    assert_eq!(json!("FTX"), order["externalReferralProgram"]);
+
+    let invalid = |order: OrderRequest| match order.validate() {
+        Err(Error::InvalidOrder(_)) => {}
+        result => panic!("Invalid order error expected, got {:?}.", result),
+    };
+    invalid(OrderRequest::market("BTC-PERP", Side::Buy, dec!(0.01)).post_only(true));
+    invalid(OrderRequest::market("BTC-PERP", Side::Buy, dec!(0)));
+    invalid(OrderRequest::limit(
+        "BTC-PERP",
+        Side::Buy,
+        dec!(-1),
+        dec!(0.01),
+    ));
+    invalid(
+        OrderRequest::limit("BTC-PERP", Side::Buy, dec!(40000), dec!(0.01))
+            .post_only(true)
+            .ioc(true),
+    );
+    invalid(OrderRequest::new(
+        "BTC-PERP",
+        Side::Buy,
+        OrderType::Market,
+        Some(dec!(40000)),
+        dec!(0.01),
+    ));
+    match OrderRequest::new("BTC-PERP", Side::Buy, OrderType::Limit, None, dec!(0.01)).validate() {
+        Err(Error::PlacingLimitOrderRequiresPrice) => {}
+        result => panic!("Missing price error expected, got {:?}.", result),
+    }
+}
+
+#[tokio::test]
+async fn rate_limiter() {
+    let limiter = RateLimiter::new(2, std::time::Duration::from_millis(200));
+    let shared = limiter.clone();
+
+    let start = std::time::Instant::now();
+    limiter.acquire().await;
+    shared.acquire().await;
+    assert!(start.elapsed() < std::time::Duration::from_millis(100));
+
+    // The third request has to wait for the first to leave the window
+    limiter.acquire().await;
+    assert!(start.elapsed() >= std::time::Duration::from_millis(200));
+}
+
+#[tokio::test]
+async fn replay_fixtures() {
+    let dir = std::env::temp_dir().join(format!("ftx-fixtures-{}", std::process::id()));
+    let path = "/markets/BTC-PERP";
+    let response = r#"{"success":true,"result":{
+        "name":"BTC-PERP",
+        "type":"future",
+        "underlying":"BTC",
+        "baseCurrency":null,
+        "quoteCurrency":null,
+        "enabled":true,
+        "ask":40001.0,
+        "bid":40000.0,
+        "last":40000.5,
+        "postOnly":false,
+        "priceIncrement":1.0,
+        "sizeIncrement":0.0001,
+        "restricted":false,
+        "minProvideSize":0.001,
+        "price":40000.5,
+        "highLeverageFeeExempt":false,
+        "change1h":0.01,
+        "change24h":-0.02,
+        "changeBod":0.0,
+        "quoteVolume24h":1234567.8,
+        "volumeUsd24h":1234567.8
+    }}"#;
+    Fixtures::new(&dir, FixtureMode::Record)
+        .save(
+            &Method::GET,
+            path,
+            &None,
+            "",
+            StatusCode::OK,
+            response.as_bytes(),
+        )
+        .unwrap();
+
+    let api = Rest::new(String::new(), String::new(), None, None)
+        .unwrap()
+        .with_fixtures(&dir, FixtureMode::Replay);
+    let market = api.get_market("BTC-PERP").await.unwrap();
+    assert_eq!("BTC-PERP", market.name);
+    assert_eq!(dec!(40000.5), market.price);
+
+    match api.get_market("ETH-PERP").await {
+        Err(Error::MissingFixture(_)) => {}
+        _ => panic!("Missing fixture error expected."),
+    }
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn invalid_proxy() {
+    match Rest::new(
+        String::new(),
+        String::new(),
+        None,
+        Some("not a proxy".to_string()),
+    ) {
+        Err(Error::Reqwest(_)) => {}
+        _ => panic!("Invalid proxy error expected."),
+    }
 }
 
 #[tokio::test]
@@ -224,15 +751,13 @@ pub async fn manipulate_orders() {
     // Test place order
     let initial_order = api
         .place_order(
-            market.as_str(),
-            Side::Buy,
-            Some(initial_bid_price),
-            OrderType::Limit,
-            initial_bid_size,
-            None,
-            None,
-            Some(true),
-            None,
+            OrderRequest::limit(
+                market.as_str(),
+                Side::Buy,
+                initial_bid_price,
+                initial_bid_size,
+            )
+            .post_only(true),
         )
         .await
         .unwrap();
@@ -277,15 +802,13 @@ pub async fn manipulate_orders() {
     let rejected_bid_price = dec!(1.1) * price; // Bid at 110% of current price
     let rejected_order = api
         .place_order(
-            market.as_str(),
-            Side::Buy,
-            Some(rejected_bid_price),
-            OrderType::Limit,
-            initial_bid_size,
-            None,
-            None,
-            Some(true),
-            None,
+            OrderRequest::limit(
+                market.as_str(),
+                Side::Buy,
+                rejected_bid_price,
+                initial_bid_size,
+            )
+            .post_only(true),
         )
         .await
         .unwrap();
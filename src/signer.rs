@@ -0,0 +1,127 @@
+//! This module signs requests to the REST and websocket APIs.
+
+use hmac_sha256::HMAC;
+use serde_json::{json, Value};
+use std::fmt;
+use zeroize::Zeroizing;
+
+/// Signs requests with an API secret, which is wiped from memory on drop.
+///
+/// See https://docs.ftx.com/#authentication.
+#[derive(Clone)]
+pub struct Signer {
+    secret: Zeroizing<Vec<u8>>,
+}
+
+impl Signer {
+    pub fn new(secret: String) -> Self {
+        Self {
+            secret: Zeroizing::new(secret.into_bytes()),
+        }
+    }
+
+    /// Returns the hex-encoded HMAC-SHA256 of the payload.
+    pub fn sign(&self, payload: &[u8]) -> String {
+        hex::encode(HMAC::mac(payload, &self.secret))
+    }
+
+    /// Returns the `FTX-SIGN` header of a REST request. `path` includes the
+    /// `/api` prefix and the query string, e.g. `/api/orders?market=BTC-PERP`.
+    pub fn sign_rest(&self, timestamp: u64, method: &str, path: &str, body: &str) -> String {
+        self.sign(rest_payload(timestamp, method, path, body).as_bytes())
+    }
+
+    /// Returns the websocket message that authenticates the connection.
+    pub fn ws_login(&self, key: &str, timestamp: u64, subaccount: Option<&str>) -> Value {
+        json!({
+            "op": "login",
+            "args": {
+                "key": key,
+                "sign": self.sign(ws_payload(timestamp).as_bytes()),
+                "time": timestamp,
+                "subaccount": subaccount,
+            }
+        })
+    }
+
+    /// Checks a hex-encoded signature of the payload in constant time.
+    pub fn verify(&self, payload: &[u8], signature: &str) -> bool {
+        let expected = self.sign(payload);
+        expected.len() == signature.len()
+            && expected
+                .bytes()
+                .zip(signature.bytes())
+                .fold(0, |diff, (a, b)| diff | (a ^ b))
+                == 0
+    }
+
+    /// Checks the `FTX-SIGN` header of a REST request.
+    pub fn verify_rest(
+        &self,
+        timestamp: u64,
+        method: &str,
+        path: &str,
+        body: &str,
+        signature: &str,
+    ) -> bool {
+        self.verify(
+            rest_payload(timestamp, method, path, body).as_bytes(),
+            signature,
+        )
+    }
+
+    /// Checks the `sign` argument of a websocket login message.
+    pub fn verify_ws_login(&self, timestamp: u64, signature: &str) -> bool {
+        self.verify(ws_payload(timestamp).as_bytes(), signature)
+    }
+}
+
+impl fmt::Debug for Signer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Signer")
+            .field("secret", &"<redacted>")
+            .finish()
+    }
+}
+
+fn rest_payload(timestamp: u64, method: &str, path: &str, body: &str) -> String {
+    format!("{}{}{}{}", timestamp, method, path, body)
+}
+
+fn ws_payload(timestamp: u64) -> String {
+    format!("{}websocket_login", timestamp)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sign_and_verify() {
+        // Example from https://docs.ftx.com/#authentication
+        let signer = Signer::new("T4lPid48QtjNxjLUFOcUZghD7CUJ7sTVsfuvQZF2".to_string());
+
+        let signature = signer.sign_rest(1588591511721, "GET", "/api/markets", "");
+        assert_eq!(
+            "dbc62ec300b2624c580611858d94f2332ac636bb86eccfa1167a7777c496ee6f",
+            signature
+        );
+        assert!(signer.verify_rest(1588591511721, "GET", "/api/markets", "", &signature));
+        assert!(!signer.verify_rest(1588591511722, "GET", "/api/markets", "", &signature));
+        assert!(!Signer::new("other".to_string()).verify_rest(
+            1588591511721,
+            "GET",
+            "/api/markets",
+            "",
+            &signature
+        ));
+
+        let login = signer.ws_login("key", 1588591511721, Some("Bot"));
+        assert_eq!("login", login["op"]);
+        assert_eq!("Bot", login["args"]["subaccount"]);
+        let sign = login["args"]["sign"].as_str().unwrap();
+        assert!(signer.verify_ws_login(1588591511721, sign));
+
+        assert_eq!("Signer { secret: \"<redacted>\" }", format!("{:?}", signer));
+    }
+}
@@ -0,0 +1,291 @@
+//! This module persists account activity to Postgres. It is only available
+//! with the `postgres` feature.
+//!
+//! `PgStore::migrate` creates the enum types and tables from
+//! `migrations/postgres`. All writes are upserts keyed by the FTX id, so the
+//! same fill or order can be stored again, e.g. after a websocket update or
+//! when re-syncing over REST, without creating duplicates.
+
+mod error;
+#[cfg(test)]
+mod tests;
+
+pub use error::*;
+
+use crate::rest::{ConditionalOrderInfo, FillInfo, FundingPayment, Id, OrderInfo, OrderStatus};
+use serde_json::Value;
+use sqlx::error::BoxDynError;
+use sqlx::postgres::{PgArgumentBuffer, PgConnection, PgPool, PgTypeInfo, PgValueRef, Postgres};
+use sqlx::{encode::IsNull, Decode, Encode, Type};
+
+#[derive(Clone, Debug)]
+pub struct PgStore {
+    pool: PgPool,
+}
+
+impl PgStore {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Connects to the database at `url`, e.g. `postgres://localhost/ftx`.
+    pub async fn connect(url: &str) -> Result<Self> {
+        Ok(Self::new(PgPool::connect(url).await?))
+    }
+
+    pub fn pool(&self) -> &PgPool {
+        &self.pool
+    }
+
+    /// Applies the migrations that have not been run on the database yet.
+    pub async fn migrate(&self) -> Result<()> {
+        sqlx::migrate!("migrations/postgres")
+            .run(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn upsert_fill(&self, fill: &FillInfo) -> Result<()> {
+        self.upsert_fills(std::slice::from_ref(fill)).await
+    }
+
+    /// Upserts the fills in a single transaction.
+    pub async fn upsert_fills(&self, fills: &[FillInfo]) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+        for fill in fills {
+            upsert_fill(&mut tx, fill).await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    pub async fn upsert_order(&self, order: &OrderInfo) -> Result<()> {
+        self.upsert_orders(std::slice::from_ref(order)).await
+    }
+
+    /// Upserts the orders in a single transaction.
+    pub async fn upsert_orders(&self, orders: &[OrderInfo]) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+        for order in orders {
+            upsert_order(&mut tx, order).await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    pub async fn upsert_conditional_order(&self, order: &ConditionalOrderInfo) -> Result<()> {
+        self.upsert_conditional_orders(std::slice::from_ref(order))
+            .await
+    }
+
+    /// Upserts the conditional orders in a single transaction.
+    pub async fn upsert_conditional_orders(&self, orders: &[ConditionalOrderInfo]) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+        for order in orders {
+            upsert_conditional_order(&mut tx, order).await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    pub async fn upsert_funding_payment(&self, payment: &FundingPayment) -> Result<()> {
+        self.upsert_funding_payments(std::slice::from_ref(payment))
+            .await
+    }
+
+    /// Upserts the funding payments in a single transaction.
+    pub async fn upsert_funding_payments(&self, payments: &[FundingPayment]) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+        for payment in payments {
+            upsert_funding_payment(&mut tx, payment).await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Stored fills, oldest first, optionally only those of one market.
+    pub async fn get_fills(&self, market: Option<&str>) -> Result<Vec<FillInfo>> {
+        Ok(sqlx::query_as(
+            "SELECT * FROM fills WHERE $1::TEXT IS NULL OR market = $1 ORDER BY time, id",
+        )
+        .bind(market)
+        .fetch_all(&self.pool)
+        .await?)
+    }
+
+    pub async fn get_order(&self, id: Id) -> Result<Option<OrderInfo>> {
+        Ok(sqlx::query_as("SELECT * FROM orders WHERE id = $1")
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await?)
+    }
+
+    pub async fn get_conditional_order(&self, id: Id) -> Result<Option<ConditionalOrderInfo>> {
+        Ok(
+            sqlx::query_as("SELECT * FROM conditional_orders WHERE id = $1")
+                .bind(id)
+                .fetch_optional(&self.pool)
+                .await?,
+        )
+    }
+
+    /// Stored funding payments, oldest first, optionally only those of one
+    /// future.
+    pub async fn get_funding_payments(&self, future: Option<&str>) -> Result<Vec<FundingPayment>> {
+        Ok(sqlx::query_as(
+            "SELECT * FROM funding_payments WHERE $1::TEXT IS NULL OR future = $1 \
+             ORDER BY time, id",
+        )
+        .bind(future)
+        .fetch_all(&self.pool)
+        .await?)
+    }
+}
+
+async fn upsert_fill(conn: &mut PgConnection, fill: &FillInfo) -> Result<()> {
+    sqlx::query(
+        "INSERT INTO fills (id, market, future, type, side, price, size, time, fee, \
+         fee_currency, fee_rate, liquidity, base_currency, quote_currency, order_id, trade_id) \
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16) \
+         ON CONFLICT (id) DO UPDATE SET market = $2, future = $3, type = $4, side = $5, \
+         price = $6, size = $7, time = $8, fee = $9, fee_currency = $10, fee_rate = $11, \
+         liquidity = $12, base_currency = $13, quote_currency = $14, order_id = $15, \
+         trade_id = $16",
+    )
+    .bind(fill.id)
+    .bind(&fill.market)
+    .bind(&fill.future)
+    .bind(fill.r#type)
+    .bind(fill.side)
+    .bind(fill.price)
+    .bind(fill.size)
+    .bind(fill.time)
+    .bind(fill.fee)
+    .bind(&fill.fee_currency)
+    .bind(fill.fee_rate)
+    .bind(fill.liquidity)
+    .bind(&fill.base_currency)
+    .bind(&fill.quote_currency)
+    .bind(fill.order_id)
+    .bind(fill.trade_id)
+    .execute(conn)
+    .await?;
+    Ok(())
+}
+
+async fn upsert_order(conn: &mut PgConnection, order: &OrderInfo) -> Result<()> {
+    sqlx::query(
+        "INSERT INTO orders (id, market, future, type, side, price, size, reduce_only, ioc, \
+         post_only, status, filled_size, remaining_size, avg_fill_price, liquidation, \
+         created_at, client_id) \
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17) \
+         ON CONFLICT (id) DO UPDATE SET market = $2, future = $3, type = $4, side = $5, \
+         price = $6, size = $7, reduce_only = $8, ioc = $9, post_only = $10, status = $11, \
+         filled_size = $12, remaining_size = $13, avg_fill_price = $14, liquidation = $15, \
+         created_at = $16, client_id = $17",
+    )
+    .bind(order.id)
+    .bind(&order.market)
+    .bind(&order.future)
+    .bind(order.r#type)
+    .bind(order.side)
+    .bind(order.price)
+    .bind(order.size)
+    .bind(order.reduce_only)
+    .bind(order.ioc)
+    .bind(order.post_only)
+    .bind(&order.status)
+    .bind(order.filled_size)
+    .bind(order.remaining_size)
+    .bind(order.avg_fill_price)
+    .bind(order.liquidation)
+    .bind(order.created_at)
+    .bind(&order.client_id)
+    .execute(conn)
+    .await?;
+    Ok(())
+}
+
+async fn upsert_conditional_order(
+    conn: &mut PgConnection,
+    order: &ConditionalOrderInfo,
+) -> Result<()> {
+    sqlx::query(
+        "INSERT INTO conditional_orders (id, market, future, order_type, type, side, price, \
+         size, status, trail_start, trail_value, trigger_price, triggered_at, filled_size, \
+         avg_fill_price, created_at, client_id, order_price, retry_until_filled) \
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17, \
+         $18, $19) \
+         ON CONFLICT (id) DO UPDATE SET market = $2, future = $3, order_type = $4, type = $5, \
+         side = $6, price = $7, size = $8, status = $9, trail_start = $10, trail_value = $11, \
+         trigger_price = $12, triggered_at = $13, filled_size = $14, avg_fill_price = $15, \
+         created_at = $16, client_id = $17, order_price = $18, retry_until_filled = $19",
+    )
+    .bind(order.id)
+    .bind(&order.market)
+    .bind(&order.future)
+    .bind(order.order_type)
+    .bind(order.r#type)
+    .bind(order.side)
+    .bind(order.price)
+    .bind(order.size)
+    .bind(order.status)
+    .bind(order.trail_start)
+    .bind(order.trail_value)
+    .bind(order.trigger_price)
+    .bind(order.triggered_at)
+    .bind(order.filled_size)
+    .bind(order.avg_fill_price)
+    .bind(order.created_at)
+    .bind(&order.client_id)
+    .bind(order.order_price)
+    .bind(order.retry_until_filled)
+    .execute(conn)
+    .await?;
+    Ok(())
+}
+
+async fn upsert_funding_payment(conn: &mut PgConnection, payment: &FundingPayment) -> Result<()> {
+    sqlx::query(
+        "INSERT INTO funding_payments (id, future, payment, time) VALUES ($1, $2, $3, $4) \
+         ON CONFLICT (id) DO UPDATE SET future = $2, payment = $3, time = $4",
+    )
+    .bind(payment.id)
+    .bind(&payment.future)
+    .bind(payment.payment)
+    .bind(payment.time)
+    .execute(conn)
+    .await?;
+    Ok(())
+}
+
+// `OrderStatus` has an `Unknown` variant and therefore cannot derive
+// `sqlx::Type`. It is stored as text, so that unknown statuses are kept
+// rather than failing the whole batch they are upserted in.
+
+impl Type<Postgres> for OrderStatus {
+    fn type_info() -> PgTypeInfo {
+        <str as Type<Postgres>>::type_info()
+    }
+
+    fn compatible(ty: &PgTypeInfo) -> bool {
+        <str as Type<Postgres>>::compatible(ty)
+    }
+}
+
+impl Encode<'_, Postgres> for OrderStatus {
+    fn encode_by_ref(&self, buf: &mut PgArgumentBuffer) -> IsNull {
+        let name = match serde_json::to_value(self) {
+            Ok(Value::String(name)) => name,
+            _ => unreachable!("statuses serialize to strings"),
+        };
+        <&str as Encode<Postgres>>::encode(&name, buf)
+    }
+}
+
+impl<'r> Decode<'r, Postgres> for OrderStatus {
+    fn decode(value: PgValueRef<'r>) -> std::result::Result<Self, BoxDynError> {
+        let name = <&str as Decode<Postgres>>::decode(value)?;
+        Ok(serde_json::from_value(Value::String(name.to_string()))?)
+    }
+}
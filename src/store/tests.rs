@@ -0,0 +1,105 @@
+use super::*;
+use crate::rest::{ConditionalOrderStatus, OrderStatus};
+use dotenv::dotenv;
+use rust_decimal_macros::dec;
+use std::env::var;
+
+async fn init_store() -> PgStore {
+    dotenv().ok();
+
+    let store = PgStore::connect(&var("DATABASE_URL").expect("DATABASE_URL is not defined."))
+        .await
+        .unwrap();
+    store.migrate().await.unwrap();
+    store
+}
+
+#[tokio::test]
+async fn upserts() {
+    let store = init_store().await;
+
+    let mut order: OrderInfo = serde_json::from_str(
+        r#"{
+            "id":-9596912,"market":"XRP-PERP","future":"XRP-PERP","type":"limit",
+            "side":"sell","price":0.306525,"size":31431,"reduceOnly":false,"ioc":false,
+            "postOnly":false,"status":"open","filledSize":0,"remainingSize":31431,
+            "avgFillPrice":null,"liquidation":null,
+            "createdAt":"2019-03-05T09:56:55.728933+00:00","clientId":"store test"
+        }"#,
+    )
+    .unwrap();
+    store.upsert_order(&order).await.unwrap();
+    order.status = OrderStatus::Closed;
+    order.filled_size = dec!(31431);
+    order.remaining_size = dec!(0);
+    order.avg_fill_price = Some(dec!(0.306525));
+    store.upsert_order(&order).await.unwrap();
+    assert_eq!(
+        Some(order.clone()),
+        store.get_order(-9596912).await.unwrap()
+    );
+
+    // Statuses this version does not know are stored as they are, and do not
+    // fail the rest of the batch
+    let mut unknown = order.clone();
+    unknown.id = -9596913;
+    unknown.status = OrderStatus::Unknown("triggered".to_string());
+    store
+        .upsert_orders(&[order, unknown.clone()])
+        .await
+        .unwrap();
+    assert_eq!(Some(unknown), store.get_order(-9596913).await.unwrap());
+
+    let mut conditional: ConditionalOrderInfo = serde_json::from_str(
+        r#"{
+            "id":-50001,"market":"XRP-PERP","future":"XRP-PERP","orderType":"market",
+            "type":"trailing_stop","side":"buy","price":null,"size":0.003,
+            "status":"open","trailStart":0.3,"trailValue":-0.01,"triggerPrice":0.306,
+            "triggeredAt":null,"filledSize":0,"avgFillPrice":null,
+            "createdAt":"2019-03-16T22:11:30.133476+00:00","clientId":null,
+            "orderPrice":null,"retryUntilFilled":true
+        }"#,
+    )
+    .unwrap();
+    store.upsert_conditional_order(&conditional).await.unwrap();
+    conditional.status = ConditionalOrderStatus::Cancelled;
+    store.upsert_conditional_order(&conditional).await.unwrap();
+    assert_eq!(
+        Some(conditional),
+        store.get_conditional_order(-50001).await.unwrap()
+    );
+
+    let fill: FillInfo = serde_json::from_str(
+        r#"{
+            "id":-11,"market":"STORE-TEST","future":null,"type":"order","side":"buy",
+            "price":10050.5,"size":0.001,"time":"2019-03-05T09:56:55.728933+00:00",
+            "fee":0.0000201,"feeCurrency":"USD","feeRate":0.0002,"liquidity":"taker",
+            "baseCurrency":null,"quoteCurrency":null,"orderId":-9596912,"tradeId":1034
+        }"#,
+    )
+    .unwrap();
+    store
+        .upsert_fills(&[fill.clone(), fill.clone()])
+        .await
+        .unwrap();
+    assert_eq!(
+        vec![fill],
+        store.get_fills(Some("STORE-TEST")).await.unwrap()
+    );
+
+    let payment: FundingPayment = serde_json::from_str(
+        r#"{
+            "id":-1,"future":"STORE-TEST-PERP","payment":0.0123,
+            "time":"2019-03-05T10:00:00+00:00"
+        }"#,
+    )
+    .unwrap();
+    store.upsert_funding_payment(&payment).await.unwrap();
+    assert_eq!(
+        vec![payment],
+        store
+            .get_funding_payments(Some("STORE-TEST-PERP"))
+            .await
+            .unwrap()
+    );
+}
@@ -15,9 +15,44 @@ pub enum Error {
     #[error("Socket is not authenticated")]
     SocketNotAuthenticated,
 
+    #[error("Invalid proxy address {0:?}")]
+    InvalidProxy(String),
+
+    #[error("Connection closed")]
+    ConnectionClosed,
+
+    #[error("Market {0} is missing the prices and volumes of a REST market")]
+    IncompleteMarket(String),
+
+    #[error(transparent)]
+    Credentials(#[from] crate::credentials::Error),
+
+    /// Boxed, as it is much larger than the other variants.
+    #[error(transparent)]
+    Tungstenite(Box<tungstenite::Error>),
+
     #[error(transparent)]
-    Tungstenite(#[from] tungstenite::Error),
+    Proxy(#[from] tokio_socks::Error),
+
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
 
     #[error(transparent)]
     Serde(#[from] serde_json::Error),
 }
+
+impl From<tungstenite::Error> for Error {
+    fn from(error: tungstenite::Error) -> Self {
+        Self::Tungstenite(Box::new(error))
+    }
+}
+
+impl Error {
+    /// Whether the error means that the connection is lost.
+    pub fn is_disconnect(&self) -> bool {
+        matches!(
+            self,
+            Self::ConnectionClosed | Self::Tungstenite(_) | Self::Proxy(_) | Self::Io(_)
+        )
+    }
+}
@@ -8,14 +8,16 @@ mod tests;
 pub use error::*;
 pub use model::*;
 
+use crate::clock::Clock;
+use crate::signer::Signer;
 use futures::{
     ready,
     task::{Context, Poll},
     Future, SinkExt, Stream, StreamExt,
 };
-use hmac_sha256::HMAC;
-use serde_json::json;
-use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use serde::Serialize;
+use serde_json::{json, value::RawValue};
+use std::time::Duration;
 use std::{collections::VecDeque, str::FromStr};
 use std::{pin::Pin, sync::Arc};
 use tokio::net::TcpStream;
@@ -27,11 +29,13 @@ use tokio_tungstenite::{connect_async, tungstenite::Message};
 #[derive(Debug)]
 pub enum GenericWebSocketStream {
     /// Direct socket stream.
-    Plain(tokio_tungstenite::WebSocketStream<tokio_tungstenite::MaybeTlsStream<TcpStream>>),
+    Plain(Box<tokio_tungstenite::WebSocketStream<tokio_tungstenite::MaybeTlsStream<TcpStream>>>),
     /// Proxied socket stream.
     Proxy(
-        tokio_tungstenite::WebSocketStream<
-            tokio_rustls::client::TlsStream<tokio_socks::tcp::Socks5Stream<TcpStream>>,
+        Box<
+            tokio_tungstenite::WebSocketStream<
+                tokio_rustls::client::TlsStream<tokio_socks::tcp::Socks5Stream<TcpStream>>,
+            >,
         >,
     ),
 }
@@ -62,6 +66,34 @@ impl GenericWebSocketStream {
     }
 }
 
+/// The delays between attempts to reconnect, see `Ws::with_reconnect`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Backoff {
+    /// The delay before the first attempt, doubled after every failed one.
+    pub initial: Duration,
+    /// The longest delay between two attempts.
+    pub max: Duration,
+}
+
+impl Default for Backoff {
+    fn default() -> Self {
+        Self {
+            initial: Duration::from_secs(1),
+            max: Duration::from_secs(60),
+        }
+    }
+}
+
+/// What is needed to open the connection again.
+#[derive(Clone)]
+struct Connection {
+    endpoint: String,
+    login: Option<(String, Signer)>,
+    subaccount: Option<String>,
+    proxy: Option<String>,
+    clock: Clock,
+}
+
 pub struct Ws {
     channels: Vec<Channel>,
     stream: GenericWebSocketStream,
@@ -69,8 +101,15 @@ pub struct Ws {
     ping_timer: Interval,
     /// Whether the websocket was opened authenticated with API keys or not
     is_authenticated: bool,
+    connection: Connection,
+    backoff: Option<Backoff>,
+    /// The pending attempt to reconnect and its delay. It is kept here, as
+    /// the future polled by `Stream` does not outlive a single poll.
+    reconnecting: Option<(Duration, Reconnect)>,
 }
 
+type Reconnect = Pin<Box<dyn Future<Output = Result<GenericWebSocketStream>> + Send>>;
+
 impl Ws {
     pub const ENDPOINT: &'static str = "ftx.com";
 
@@ -79,66 +118,70 @@ impl Ws {
         key_secret: Option<(String, String)>,
         subaccount: Option<String>,
         proxy: Option<String>,
+        clock: &Clock,
     ) -> Result<Self> {
-        let mut stream: GenericWebSocketStream = match proxy {
+        let connection = Connection {
+            endpoint: endpoint.to_string(),
+            login: key_secret.map(|(key, secret)| (key, Signer::new(secret))),
+            subaccount,
+            proxy,
+            clock: clock.clone(),
+        };
+        Ok(Self {
+            channels: Vec::new(),
+            stream: Self::open(&connection).await?,
+            buf: VecDeque::new(),
+            ping_timer: time::interval(Duration::from_secs(15)),
+            is_authenticated: connection.login.is_some(),
+            connection,
+            backoff: None,
+            reconnecting: None,
+        })
+    }
+
+    /// Opens the socket and logs in if there are credentials.
+    async fn open(connection: &Connection) -> Result<GenericWebSocketStream> {
+        let endpoint = &connection.endpoint;
+        let mut stream = match &connection.proxy {
             Some(proxy) => {
-                let socks_stream = tokio_socks::tcp::Socks5Stream::connect(
-                    std::net::SocketAddr::from_str(&proxy).expect("invalid proxy addr"),
-                    (Self::ENDPOINT, 443),
-                )
-                .await
-                .expect("cannot connect to proxy");
+                let proxy = std::net::SocketAddr::from_str(proxy)
+                    .map_err(|_| Error::InvalidProxy(proxy.clone()))?;
+                let socks_stream =
+                    tokio_socks::tcp::Socks5Stream::connect(proxy, (Self::ENDPOINT, 443)).await?;
                 let mut client_config = tokio_rustls::rustls::ClientConfig::new();
                 client_config
                     .root_store
                     .add_server_trust_anchors(&webpki_roots::TLS_SERVER_ROOTS);
                 let connector = tokio_rustls::TlsConnector::from(Arc::new(client_config));
                 let domain = webpki::DNSNameRef::try_from_ascii_str(Self::ENDPOINT).unwrap();
-                let tls_stream = connector
-                    .connect(domain, socks_stream)
-                    .await
-                    .expect("cannot create tls stream");
+                let tls_stream = connector.connect(domain, socks_stream).await?;
                 let (ws_client, _) =
                     tokio_tungstenite::client_async(format!("wss://{}/ws", endpoint), tls_stream)
-                        .await
-                        .unwrap();
-                GenericWebSocketStream::Proxy(ws_client)
+                        .await?;
+                GenericWebSocketStream::Proxy(Box::new(ws_client))
+            }
+            None => {
+                GenericWebSocketStream::Plain(Box::new(connect_async(endpoint.as_str()).await?.0))
             }
-            None => GenericWebSocketStream::Plain(connect_async(endpoint).await?.0),
         };
-        let is_authenticated = key_secret.is_some();
-        if let Some((key, secret)) = key_secret {
-            let timestamp = SystemTime::now()
-                .duration_since(UNIX_EPOCH)
-                .unwrap()
-                .as_millis();
-            let sign_payload = format!("{}websocket_login", timestamp);
-            let sign = HMAC::mac(sign_payload.as_bytes(), secret.as_bytes());
-            let sign = hex::encode(sign);
-
-            stream
-                .send(Message::Text(
-                    json!({
-                        "op": "login",
-                        "args": {
-                            "key": key,
-                            "sign": sign,
-                            "time": timestamp as u64,
-                            "subaccount": subaccount,
-                        }
-                    })
-                    .to_string(),
-                ))
-                .await?;
+        if let Some((key, signer)) = &connection.login {
+            let timestamp = connection.clock.now();
+            let login = signer.ws_login(key, timestamp, connection.subaccount.as_deref());
+
+            stream.send(Message::Text(login.to_string())).await?;
         }
-        Ok(Self {
-            channels: Vec::new(),
-            stream,
-            buf: VecDeque::new(),
-            ping_timer: time::interval(Duration::from_secs(15)),
-            is_authenticated,
-        })
+        Ok(stream)
+    }
+
+    /// Reconnects when the connection drops, instead of returning an error,
+    /// waiting longer after every failed attempt. Once connected, the socket
+    /// logs in and subscribes to all channels again, and the stream yields
+    /// `Data::Reconnected`.
+    pub fn with_reconnect(mut self, backoff: Backoff) -> Self {
+        self.backoff = Some(backoff);
+        self
     }
+
     pub async fn connect(
         // Pair (API_KEY, SECRET_KEY) for authentification.
         // The channels FILL, ORDER, and FTX Pay require authentification
@@ -146,7 +189,18 @@ impl Ws {
         subaccount: Option<String>,
         proxy: Option<String>,
     ) -> Result<Self> {
-        Self::connect_with_endpoint(Self::ENDPOINT, key_secret, subaccount, proxy).await
+        Self::connect_with_clock(key_secret, subaccount, proxy, &Clock::new()).await
+    }
+
+    /// Like `connect`, but timestamps the login with a clock synchronized to
+    /// the server, e.g. by `Rest::sync_time`.
+    pub async fn connect_with_clock(
+        key_secret: Option<(String, String)>,
+        subaccount: Option<String>,
+        proxy: Option<String>,
+        clock: &Clock,
+    ) -> Result<Self> {
+        Self::connect_with_endpoint(Self::ENDPOINT, key_secret, subaccount, proxy, clock).await
     }
 
     // Pair (API_KEY, SECRET_KEY) for authentification.
@@ -180,7 +234,6 @@ impl Ws {
             {
                 return Err(Error::SocketNotAuthenticated);
             }
-            self.channels.push(channel.clone());
         }
 
         self.subscribe_or_unsubscribe(channels, true).await?;
@@ -197,11 +250,7 @@ impl Ws {
             }
         }
 
-        self.subscribe_or_unsubscribe(channels.clone(), false)
-            .await?;
-
-        // Unsubscribe successful, remove specified channels from self.channels
-        self.channels.retain(|c| !channels.contains(c));
+        self.subscribe_or_unsubscribe(channels, false).await?;
 
         Ok(())
     }
@@ -220,55 +269,40 @@ impl Ws {
         channels: Vec<Channel>,
         subscribe: bool,
     ) -> Result<()> {
-        let op = if subscribe {
-            "subscribe"
+        let (op, confirmation) = if subscribe {
+            ("subscribe", Type::Subscribed)
         } else {
-            "unsubscribe"
+            ("unsubscribe", Type::Unsubscribed)
         };
 
         'channels: for channel in channels {
-            let (channel, symbol) = match channel {
-                Channel::Orderbook(symbol) => ("orderbook", symbol),
-                Channel::Trades(symbol) => ("trades", symbol),
-                Channel::Ticker(symbol) => ("ticker", symbol),
-                Channel::Fills => ("fills", "".to_string()),
-                Channel::Orders => ("orders", "".to_string()),
-            };
-
-            self.stream
-                .send(Message::Text(
-                    json!({
-                        "op": op,
-                        "channel": channel,
-                        "market": symbol,
-                    })
-                    .to_string(),
-                ))
-                .await?;
+            // Reconnecting subscribes to `self.channels`, so it must not
+            // contain a channel that is being unsubscribed from
+            if !subscribe {
+                self.channels.retain(|c| c != &channel);
+            }
+            self.stream.send(subscription(op, &channel)).await?;
 
             // Confirmation should arrive within the next 100 updates
             for _ in 0..100 {
-                let response = self.next_response().await?;
-                match response {
-                    Response {
-                        r#type: Type::Subscribed,
-                        ..
-                    } if subscribe => {
-                        // Subscribe confirmed
-                        continue 'channels;
-                    }
-                    Response {
-                        r#type: Type::Unsubscribed,
-                        ..
-                    } if !subscribe => {
-                        // Unsubscribe confirmed
-                        continue 'channels;
+                let response = match self.next_response().await? {
+                    Some(response) => response,
+                    // Reconnected without the pending subscription, so it is
+                    // sent again, whereas an unsubscription is complete
+                    None if subscribe => {
+                        self.stream.send(subscription(op, &channel)).await?;
+                        continue;
                     }
-                    _ => {
-                        // Otherwise, continue adding contents to buffer
-                        self.handle_response(response);
+                    None => continue 'channels,
+                };
+                if response.r#type == confirmation && is_for(&response, &channel) {
+                    if subscribe {
+                        self.channels.push(channel);
                     }
+                    continue 'channels;
                 }
+                // Otherwise, continue adding contents to buffer
+                self.handle_response(response);
             }
 
             return Err(Error::MissingSubscriptionConfirmation);
@@ -277,30 +311,79 @@ impl Ws {
         Ok(())
     }
 
-    async fn next_response(&mut self) -> Result<Response> {
+    /// The next response, or `None` after reconnecting, when
+    /// `Data::Reconnected` is in the buffer.
+    async fn next_response(&mut self) -> Result<Option<Response>> {
         loop {
-            tokio::select! {
-                _ = self.ping_timer.tick() => {
-                    self.ping().await?;
-                },
-                Some(msg) = self.stream.next() => {
-                    let msg = msg?;
-                    if let Message::Text(text) = msg {
-                        // println!("{}", text); // Uncomment for debugging
-                        let response: Response = serde_json::from_str(&text)?;
+            if let Some((delay, attempt)) = &mut self.reconnecting {
+                let delay = *delay;
+                match attempt.await {
+                    Ok(stream) => {
+                        log::info!("Websocket reconnected");
+                        self.stream = stream;
+                        self.reconnecting = None;
+                        self.ping_timer.reset();
+                        self.buf.push_back((None, Data::Reconnected));
+                        return Ok(None);
+                    }
+                    Err(error) => {
+                        let max = self.backoff.unwrap_or_default().max;
+                        let delay = (delay * 2).min(max);
+                        log::warn!("Reconnecting failed: {}, retrying in {:?}", error, delay);
+                        self.reconnecting = Some((delay, self.reconnect(delay)));
+                    }
+                }
+                continue;
+            }
 
-                        // Don't return Pong responses
-                        if let Response { r#type: Type::Pong, .. } = response {
-                            continue;
-                        }
+            let response = tokio::select! {
+                _ = self.ping_timer.tick() => self.ping().await.map(|_| None),
+                msg = self.stream.next() => match msg {
+                    Some(Ok(Message::Text(text))) => {
+                        // println!("{}", text); // Uncomment for debugging
+                        serde_json::from_str(&text).map(Some).map_err(Error::from)
+                    }
+                    Some(Ok(_)) => Ok(None),
+                    Some(Err(error)) => Err(error.into()),
+                    None => Err(Error::ConnectionClosed),
+                },
+            };
 
-                        return Ok(response)
+            match response {
+                // Don't return Pong responses
+                Ok(Some(Response {
+                    r#type: Type::Pong, ..
+                }))
+                | Ok(None) => {}
+                Ok(Some(response)) => return Ok(Some(response)),
+                Err(error) => match self.backoff {
+                    Some(backoff) if error.is_disconnect() => {
+                        log::warn!("Websocket disconnected: {}, reconnecting", error);
+                        self.reconnecting =
+                            Some((backoff.initial, self.reconnect(backoff.initial)));
                     }
+                    _ => return Err(error),
                 },
             }
         }
     }
 
+    /// An attempt to open a new connection after `delay`, subscribed to all
+    /// channels. Confirmations are not awaited, as they are skipped like
+    /// other responses without data.
+    fn reconnect(&self, delay: Duration) -> Reconnect {
+        let connection = self.connection.clone();
+        let channels = self.channels.clone();
+        Box::pin(async move {
+            time::sleep(delay).await;
+            let mut stream = Self::open(&connection).await?;
+            for channel in &channels {
+                stream.send(subscription("subscribe", channel)).await?;
+            }
+            Ok(stream)
+        })
+    }
+
     /// Helper function that takes a response and adds the contents to the buffer
     fn handle_response(&mut self, response: Response) {
         if let Some(data) = response.data {
@@ -317,6 +400,17 @@ impl Ws {
                     self.buf
                         .push_back((response.market, Data::OrderbookData(orderbook)));
                 }
+                ResponseData::GroupedOrderbookData(mut orderbook) => {
+                    if response.r#type == Type::Partial {
+                        orderbook.action = OrderbookAction::Partial;
+                    }
+                    self.buf
+                        .push_back((response.market, Data::GroupedOrderbookData(orderbook)));
+                }
+                ResponseData::Markets(markets) => {
+                    self.buf
+                        .push_back((response.market, Data::Markets(markets)));
+                }
                 ResponseData::Fill(fill) => {
                     self.buf.push_back((response.market, Data::Fill(fill)));
                 }
@@ -331,6 +425,54 @@ impl Ws {
     }
 }
 
+/// The name and market FTX identifies a channel by in messages.
+fn channel_name(channel: &Channel) -> (&'static str, &str) {
+    match channel {
+        Channel::Orderbook(symbol) => ("orderbook", symbol),
+        Channel::GroupedOrderbook { market, .. } => ("orderbookGrouped", market),
+        Channel::Trades(symbol) => ("trades", symbol),
+        Channel::Ticker(symbol) => ("ticker", symbol),
+        Channel::Markets => ("markets", ""),
+        Channel::Fills => ("fills", ""),
+        Channel::Orders => ("orders", ""),
+    }
+}
+
+/// Whether `response` is about `channel`, e.g. confirms subscribing to it.
+fn is_for(response: &Response, channel: &Channel) -> bool {
+    let (name, market) = channel_name(channel);
+    response.channel.as_deref() == Some(name) && response.market.as_deref().unwrap_or("") == market
+}
+
+/// A request to subscribe to or unsubscribe from a channel.
+#[derive(Serialize)]
+struct Subscription<'a> {
+    op: &'a str,
+    channel: &'a str,
+    market: &'a str,
+    /// Written out as a JSON number, as a float may not hold it exactly.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    grouping: Option<Box<RawValue>>,
+}
+
+fn subscription(op: &str, channel: &Channel) -> Message {
+    let (name, market) = channel_name(channel);
+    let grouping = match channel {
+        // The digits of a decimal are always a valid JSON number
+        Channel::GroupedOrderbook { grouping, .. } => {
+            Some(RawValue::from_string(grouping.normalize().to_string()).unwrap())
+        }
+        _ => None,
+    };
+    let message = Subscription {
+        op,
+        channel: name,
+        market,
+        grouping,
+    };
+    Message::Text(serde_json::to_string(&message).unwrap())
+}
+
 impl Stream for Ws {
     type Item = Result<(Option<Symbol>, Data)>;
 
@@ -346,7 +488,8 @@ impl Stream for Ws {
                 let mut next_response = self.next_response();
                 let pinned = unsafe { Pin::new_unchecked(&mut next_response) };
                 match ready!(pinned.poll(cx)) {
-                    Ok(response) => response,
+                    Ok(Some(response)) => response,
+                    Ok(None) => continue,
                     Err(e) => {
                         return Poll::Ready(Some(Err(e)));
                     }
@@ -1,18 +1,26 @@
-pub use crate::rest::{Coin, Id, MarketType, OrderInfo, Side, Symbol};
+use super::{Error, Result};
+use crate::rest::{self, FillInfo};
+pub use crate::rest::{Coin, FillType, Id, Liquidity, MarketType, OrderInfo, Side, Symbol, Trade};
 use chrono::{DateTime, Utc};
 use crc32fast::Hasher;
 use rust_decimal::Decimal;
 use rust_decimal_macros::dec;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use serde_with::{serde_as, TimestampSecondsWithFrac};
-use std::collections::BTreeMap;
+use std::{collections::BTreeMap, convert::TryFrom};
 
-#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub enum Channel {
     Orderbook(Symbol),
     Trades(Symbol),
     Ticker(Symbol),
+    /// Orderbook levels aggregated to multiples of `grouping`.
+    GroupedOrderbook {
+        market: Symbol,
+        grouping: Decimal,
+    },
+    Markets,
     Fills,
     Orders,
 }
@@ -26,15 +34,16 @@ pub struct Response {
 }
 */
 
-#[derive(Clone, Debug, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct Response {
+    pub channel: Option<String>,
     pub market: Option<Symbol>,
     pub r#type: Type,
     pub data: Option<ResponseData>,
 }
 
-#[derive(Copy, Clone, Debug, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub enum Type {
     Subscribed,
@@ -44,17 +53,26 @@ pub enum Type {
     Partial,
     Pong,
     Info, // May need this in the future
+    /// A message type this version does not know yet. A warning is logged
+    /// the first time each one is received.
+    #[serde(
+        untagged,
+        deserialize_with = "crate::rest::deserialize_unknown::<Self, _>"
+    )]
+    Unknown(String),
 }
 
 /// Represents the response received from FTX, and is used for
 /// deserialization
-#[derive(Clone, Debug, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "camelCase")]
 #[serde(untagged)]
 pub enum ResponseData {
     Ticker(Ticker),
     Trades(Vec<Trade>),
     OrderbookData(OrderbookData),
+    GroupedOrderbookData(GroupedOrderbookData),
+    Markets(MarketsData),
     Fill(Fill),
     Order(OrderInfo),
 }
@@ -65,12 +83,18 @@ pub enum Data {
     Ticker(Ticker),
     Trade(Trade),
     OrderbookData(OrderbookData),
+    GroupedOrderbookData(GroupedOrderbookData),
+    Markets(MarketsData),
     Fill(Fill),
     Order(OrderInfo),
+    /// The connection dropped and was opened again, see `Ws::with_reconnect`.
+    /// Updates sent in between are lost, and subscribed orderbooks start over
+    /// with a `Partial`.
+    Reconnected,
 }
 
 #[serde_as]
-#[derive(Copy, Clone, Debug, Deserialize)]
+#[derive(Copy, Clone, Debug, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct Ticker {
     pub bid: Decimal,
@@ -82,21 +106,10 @@ pub struct Ticker {
     pub time: DateTime<Utc>,
 }
 
-#[derive(Copy, Clone, Debug, Deserialize)]
-#[serde(rename_all = "camelCase")]
-pub struct Trade {
-    pub id: Id,
-    pub price: Decimal,
-    pub size: Decimal,
-    pub side: Side,
-    pub liquidation: bool,
-    pub time: DateTime<Utc>, // API returns "2021-05-23T05:24:24.315884+00:00"
-}
-
 /// Order book data received from FTX which is used for initializing and updating
 /// the OrderBook struct
 #[serde_as]
-#[derive(Clone, Debug, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct OrderbookData {
     pub action: OrderbookAction,
@@ -111,12 +124,29 @@ pub struct OrderbookData {
 
 type Checksum = u32;
 
-#[derive(Copy, Clone, Debug, Deserialize, PartialEq)]
+/// Grouped orderbook data received from FTX, used to update an `Orderbook`
+/// with `Orderbook::update_grouped`. Unlike `OrderbookData`, it has neither
+/// a checksum nor a timestamp.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct GroupedOrderbookData {
+    /// Not part of the data, but taken from the type of the response.
+    #[serde(default = "grouped_action")]
+    pub action: OrderbookAction,
+    pub bids: Vec<(Decimal, Decimal)>,
+    pub asks: Vec<(Decimal, Decimal)>,
+}
+
+fn grouped_action() -> OrderbookAction {
+    OrderbookAction::Update
+}
+
+#[derive(Copy, Clone, Debug, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub enum OrderbookAction {
-    /// Initial snapshot of the orderbook
+    /// Initial snapshot of the orderbook or markets
     Partial,
-    /// Updates to the orderbook
+    /// Updates to the orderbook or markets
     Update,
 }
 
@@ -139,30 +169,38 @@ impl Orderbook {
     }
 
     pub fn update(&mut self, data: &OrderbookData) {
-        match data.action {
-            OrderbookAction::Partial => {
-                for bid in &data.bids {
-                    self.bids.insert(bid.0, bid.1);
-                }
-                for ask in &data.asks {
-                    self.asks.insert(ask.0, ask.1);
-                }
+        self.apply(data.action, &data.bids, &data.asks);
+    }
+
+    /// Applies data of the grouped orderbook channel, whose levels are
+    /// aggregated to multiples of the grouping. There is no checksum to verify.
+    pub fn update_grouped(&mut self, data: &GroupedOrderbookData) {
+        self.apply(data.action, &data.bids, &data.asks);
+    }
+
+    fn apply(
+        &mut self,
+        action: OrderbookAction,
+        bids: &[(Decimal, Decimal)],
+        asks: &[(Decimal, Decimal)],
+    ) {
+        if action == OrderbookAction::Partial {
+            // A partial replaces the whole book, e.g. after reconnecting
+            self.bids.clear();
+            self.asks.clear();
+        }
+        for bid in bids {
+            if bid.1 == dec!(0) {
+                self.bids.remove(&bid.0);
+            } else {
+                self.bids.insert(bid.0, bid.1);
             }
-            OrderbookAction::Update => {
-                for bid in &data.bids {
-                    if bid.1 == dec!(0) {
-                        self.bids.remove(&bid.0);
-                    } else {
-                        self.bids.insert(bid.0, bid.1);
-                    }
-                }
-                for ask in &data.asks {
-                    if ask.1 == dec!(0) {
-                        self.asks.remove(&ask.0);
-                    } else {
-                        self.asks.insert(ask.0, ask.1);
-                    }
-                }
+        }
+        for ask in asks {
+            if ask.1 == dec!(0) {
+                self.asks.remove(&ask.0);
+            } else {
+                self.asks.insert(ask.0, ask.1);
             }
         }
     }
@@ -213,7 +251,7 @@ impl Orderbook {
 
     /// Returns the price of the best bid
     pub fn bid_price(&self) -> Option<Decimal> {
-        self.bids.keys().rev().next().cloned()
+        self.bids.keys().next_back().cloned()
     }
 
     /// Returns the price of the best ask
@@ -230,7 +268,7 @@ impl Orderbook {
     /// Returns the price and quantity of the best bid
     /// (bid_price, bid_quantity)
     pub fn best_bid(&self) -> Option<(Decimal, Decimal)> {
-        let (price, quantity) = self.bids.iter().rev().next()?;
+        let (price, quantity) = self.bids.iter().next_back()?;
 
         Some((*price, *quantity))
     }
@@ -285,7 +323,7 @@ impl Orderbook {
     }
 }
 
-#[derive(Clone, Debug, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct Fill {
     pub id: Id,
@@ -293,7 +331,7 @@ pub struct Fill {
     pub future: Option<Symbol>,
     pub base_currency: Option<Coin>,
     pub quote_currency: Option<Coin>,
-    pub r#type: String, // e.g. "order"
+    pub r#type: FillType,
     pub side: Side,
     pub price: Decimal,
     pub size: Decimal,
@@ -306,23 +344,149 @@ pub struct Fill {
     pub liquidity: Liquidity,
 }
 
-#[derive(Copy, Clone, Debug, Deserialize, PartialEq)]
-#[serde(rename_all = "camelCase")]
-pub enum Liquidity {
-    Maker,
-    Taker,
+/// A fill received over websockets is a `FillInfo` that always belongs to
+/// an order, so it converts into one without loss.
+impl From<Fill> for FillInfo {
+    fn from(fill: Fill) -> Self {
+        Self {
+            id: fill.id,
+            market: Some(fill.market),
+            future: fill.future,
+            r#type: fill.r#type,
+            side: fill.side,
+            price: fill.price,
+            size: fill.size,
+            time: fill.time,
+            fee: fill.fee,
+            fee_currency: fill.fee_currency,
+            fee_rate: fill.fee_rate,
+            liquidity: fill.liquidity,
+            base_currency: fill.base_currency,
+            quote_currency: fill.quote_currency,
+            order_id: Some(fill.order_id),
+            trade_id: Some(fill.trade_id),
+        }
+    }
 }
 
-#[derive(Clone, Debug, Deserialize)]
+/// A market as sent by the markets channel. It has the fields of
+/// `rest::Market`, but only REST requests return the prices and volumes, so
+/// those are optional.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct Market {
-    name: Symbol,
-    enabled: bool,
-    price_increment: Decimal,
-    size_increment: Decimal,
+    pub name: Symbol,
+    pub enabled: bool,
+    pub price_increment: Decimal,
+    pub size_increment: Decimal,
     #[serde(rename = "type")]
     pub market_type: MarketType,
-    base_currency: Option<Coin>,
-    quote_currency: Option<Coin>,
-    underlying: Option<Coin>,
+    pub base_currency: Option<Coin>,
+    pub quote_currency: Option<Coin>,
+    pub underlying: Option<Coin>,
+    pub restricted: Option<bool>,
+    pub post_only: Option<bool>,
+    pub min_provide_size: Option<Decimal>,
+    pub high_leverage_fee_exempt: Option<bool>,
+    pub ask: Option<Decimal>,
+    pub bid: Option<Decimal>,
+    pub last: Option<Decimal>,
+    pub price: Option<Decimal>,
+    pub change1h: Option<Decimal>,
+    pub change24h: Option<Decimal>,
+    pub change_bod: Option<Decimal>,
+    pub quote_volume24h: Option<Decimal>,
+    pub volume_usd24h: Option<Decimal>,
+}
+
+impl From<rest::Market> for Market {
+    fn from(market: rest::Market) -> Self {
+        Self {
+            name: market.name,
+            enabled: market.enabled,
+            price_increment: market.price_increment,
+            size_increment: market.size_increment,
+            market_type: market.market_type,
+            base_currency: market.base_currency,
+            quote_currency: market.quote_currency,
+            underlying: market.underlying,
+            restricted: Some(market.restricted),
+            post_only: Some(market.post_only),
+            min_provide_size: Some(market.min_provide_size),
+            high_leverage_fee_exempt: Some(market.high_leverage_fee_exempt),
+            ask: Some(market.ask),
+            bid: Some(market.bid),
+            last: Some(market.last),
+            price: Some(market.price),
+            change1h: Some(market.change1h),
+            change24h: Some(market.change24h),
+            change_bod: Some(market.change_bod),
+            quote_volume24h: Some(market.quote_volume24h),
+            volume_usd24h: Some(market.volume_usd24h),
+        }
+    }
+}
+
+/// Fails with `Error::IncompleteMarket` unless the market has all the fields
+/// of a REST market, e.g. because it was converted from one.
+impl TryFrom<Market> for rest::Market {
+    type Error = Error;
+
+    fn try_from(market: Market) -> Result<Self> {
+        Ok(Self {
+            restricted: required(market.restricted, &market.name)?,
+            post_only: required(market.post_only, &market.name)?,
+            min_provide_size: required(market.min_provide_size, &market.name)?,
+            high_leverage_fee_exempt: required(market.high_leverage_fee_exempt, &market.name)?,
+            ask: required(market.ask, &market.name)?,
+            bid: required(market.bid, &market.name)?,
+            last: required(market.last, &market.name)?,
+            price: required(market.price, &market.name)?,
+            change1h: required(market.change1h, &market.name)?,
+            change24h: required(market.change24h, &market.name)?,
+            change_bod: required(market.change_bod, &market.name)?,
+            quote_volume24h: required(market.quote_volume24h, &market.name)?,
+            volume_usd24h: required(market.volume_usd24h, &market.name)?,
+            enabled: market.enabled,
+            price_increment: market.price_increment,
+            size_increment: market.size_increment,
+            market_type: market.market_type,
+            base_currency: market.base_currency,
+            quote_currency: market.quote_currency,
+            underlying: market.underlying,
+            name: market.name,
+        })
+    }
+}
+
+/// A field of `market` that REST markets always have.
+fn required<T>(value: Option<T>, market: &str) -> Result<T> {
+    value.ok_or_else(|| Error::IncompleteMarket(market.to_string()))
+}
+
+/// All markets by name, as kept up to date with `MarketsData::apply`.
+pub type Markets = BTreeMap<Symbol, Market>;
+
+/// Markets data received from FTX. A `Partial` lists all markets, and an
+/// `Update` the markets that were listed or changed, e.g. disabled or given
+/// new increments.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct MarketsData {
+    pub action: OrderbookAction,
+    pub data: Markets,
+}
+
+impl MarketsData {
+    /// Applies the data to `markets`. A partial replaces all markets.
+    pub fn apply(&self, markets: &mut Markets) {
+        if self.action == OrderbookAction::Partial {
+            markets.clear();
+        }
+        markets.extend(
+            self.data
+                .iter()
+                .map(|(name, market)| (name.clone(), market.clone())),
+        );
+    }
 }
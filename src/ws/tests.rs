@@ -1,21 +1,15 @@
 use super::*;
-use crate::rest::{OrderStatus, Rest};
+use crate::credentials::Credentials;
+use crate::rest::{self, FillInfo, OrderStatus, Rest};
 use dotenv::dotenv;
 use rust_decimal_macros::dec;
-use std::env::var;
+use std::{convert::TryFrom, env::var};
 async fn init_authenticated_ws() -> Ws {
-    dotenv().ok();
-    let proxy = var("SOCKS_PROXY").ok();
-    Ws::connect(
-        Some((
-            var("FTX_API_KEY").expect("API Key is not defined."),
-            var("FTX_API_SECRET").expect("API Secret is not defined."),
-        )),
-        var("FTX_SUBACCOUNT").ok(),
-        proxy,
-    )
-    .await
-    .expect("Connection failed.")
+    Credentials::from_env()
+        .expect("API credentials are not defined.")
+        .ws()
+        .await
+        .expect("Connection failed.")
 }
 async fn init_unauthenticated_ws() -> Ws {
     dotenv().ok();
@@ -28,14 +22,9 @@ async fn init_unauthenticated_ws() -> Ws {
 
 #[allow(dead_code)]
 async fn init_api() -> Rest {
-    dotenv().ok();
-
-    Rest::new(
-        var("FTX_API_KEY").expect("API Key is not defined."),
-        var("FTX_API_SECRET").expect("API Secret is not defined."),
-        var("FTX_SUBACCOUNT").ok(),
-        None,
-    )
+    let mut credentials = Credentials::from_env().expect("API credentials are not defined.");
+    credentials.proxy = None;
+    credentials.rest().unwrap()
 }
 
 #[tokio::test]
@@ -80,6 +69,23 @@ async fn trades() {
     ws.unsubscribe_all().await.expect("Unsubscribe failed");
 }
 
+#[tokio::test]
+async fn markets() {
+    let mut ws = init_unauthenticated_ws().await;
+
+    ws.subscribe(vec![Channel::Markets])
+        .await
+        .expect("Subscription failed.");
+
+    match ws.next().await.unwrap() {
+        Ok((None, Data::Markets(data))) => {
+            assert_eq!(OrderbookAction::Partial, data.action);
+            assert!(data.data.contains_key("BTC-PERP"));
+        }
+        _ => panic!("Markets data expected."),
+    }
+}
+
 #[tokio::test]
 async fn order_book_update() {
     let mut ws = init_unauthenticated_ws().await;
@@ -253,6 +259,143 @@ async fn order_book_checksum() {
     }
 }
 
+#[tokio::test]
+async fn grouped_order_book() {
+    let mut ws = init_unauthenticated_ws().await;
+
+    ws.subscribe(vec![Channel::GroupedOrderbook {
+        market: "BTC-PERP".to_string(),
+        grouping: dec!(500),
+    }])
+    .await
+    .expect("Subscription failed.");
+
+    let mut orderbook = Orderbook::new("BTC-PERP".to_string());
+    match ws.next().await.unwrap() {
+        Ok((_, Data::GroupedOrderbookData(data))) if data.action == OrderbookAction::Partial => {
+            orderbook.update_grouped(&data);
+        }
+        _ => panic!("Grouped order book snapshot data expected."),
+    }
+    assert!(orderbook
+        .bids
+        .keys()
+        .chain(orderbook.asks.keys())
+        .all(|price| (price % dec!(500)).is_zero()));
+
+    ws.unsubscribe_all().await.expect("Unsubscribe failed");
+}
+
+#[tokio::test]
+async fn grouped_order_book_update() {
+    use tokio_tungstenite::tungstenite::Message;
+
+    let channel = Channel::GroupedOrderbook {
+        market: "BTC-PERP".to_string(),
+        grouping: dec!(0.50),
+    };
+    match subscription("subscribe", &channel) {
+        Message::Text(text) => assert_eq!(
+            json!({
+                "op": "subscribe", "channel": "orderbookGrouped", "market": "BTC-PERP",
+                "grouping": 0.5,
+            }),
+            serde_json::from_str::<serde_json::Value>(&text).unwrap()
+        ),
+        message => panic!("Unexpected message {:?}.", message),
+    }
+    // More digits than a float holds are sent as they are
+    let precise = Channel::GroupedOrderbook {
+        market: "BTC-PERP".to_string(),
+        grouping: dec!(0.10000000000000000001),
+    };
+    assert_eq!(
+        Message::Text(
+            r#"{"op":"subscribe","channel":"orderbookGrouped","market":"BTC-PERP","grouping":0.10000000000000000001}"#
+                .to_string()
+        ),
+        subscription("subscribe", &precise)
+    );
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let endpoint = format!("ws://{}", listener.local_addr().unwrap());
+    let server = tokio::spawn(async move {
+        let (socket, _) = listener.accept().await.unwrap();
+        let mut socket = tokio_tungstenite::accept_async(socket).await.unwrap();
+        assert_eq!(
+            vec![(json!("subscribe"), json!("orderbookGrouped"))],
+            receive(&mut socket, 1).await
+        );
+        // Grouped orderbook data has no action, only the type of the
+        // response tells partials from updates
+        for message in [
+            json!({"type": "subscribed", "channel": "orderbookGrouped", "market": "BTC-PERP"}),
+            json!({
+                "type": "partial", "channel": "orderbookGrouped", "market": "BTC-PERP",
+                "data": {"bids": [[40000, 1.5], [39999.5, 2]], "asks": [[40000.5, 0.5]]},
+            }),
+            json!({
+                "type": "update", "channel": "orderbookGrouped", "market": "BTC-PERP",
+                "data": {"bids": [[40000, 0]], "asks": [[40001, 3]]},
+            }),
+            json!({
+                "type": "partial", "channel": "orderbookGrouped", "market": "BTC-PERP",
+                "data": {"bids": [[40000, 1]], "asks": [[40001, 1]]},
+            }),
+        ] {
+            socket
+                .send(Message::Text(message.to_string()))
+                .await
+                .unwrap();
+        }
+        while socket.next().await.is_some() {}
+    });
+
+    let mut ws = Ws::connect_with_endpoint(&endpoint, None, None, None, &Clock::new())
+        .await
+        .unwrap();
+    ws.subscribe(vec![channel]).await.unwrap();
+    let mut received = Vec::new();
+    while received.len() < 3 {
+        match ws.next().await {
+            Some(Ok((Some(market), Data::GroupedOrderbookData(data)))) => {
+                assert_eq!("BTC-PERP", market);
+                received.push(data);
+            }
+            data => panic!("Expected a grouped orderbook, got {:?}.", data),
+        }
+    }
+    let mut received = received.into_iter();
+
+    let mut orderbook = Orderbook::new("BTC-PERP".to_string());
+    let partial = received.next().unwrap();
+    assert_eq!(OrderbookAction::Partial, partial.action);
+    orderbook.update_grouped(&partial);
+    assert_eq!(Some((dec!(40000), dec!(1.5))), orderbook.best_bid());
+
+    let update = received.next().unwrap();
+    assert_eq!(OrderbookAction::Update, update.action);
+    orderbook.update_grouped(&update);
+    assert_eq!(
+        Some(((dec!(39999.5), dec!(2)), (dec!(40000.5), dec!(0.5)))),
+        orderbook.best_bid_and_ask()
+    );
+    assert_eq!(2, orderbook.asks.len());
+
+    // Another partial replaces the book
+    let partial = received.next().unwrap();
+    assert_eq!(OrderbookAction::Partial, partial.action);
+    orderbook.update_grouped(&partial);
+    assert_eq!(
+        Some(((dec!(40000), dec!(1)), (dec!(40001), dec!(1)))),
+        orderbook.best_bid_and_ask()
+    );
+    assert_eq!(1, orderbook.asks.len());
+
+    drop(ws);
+    server.await.unwrap();
+}
+
 #[tokio::test]
 async fn fills() {
     let mut ws = init_authenticated_ws().await;
@@ -263,19 +406,9 @@ async fn fills() {
 
     // A live test that buys 0.0001 BTC-PERP ($4 if BTC is at $40k)
     /*
-    use crate::rest::{Side, OrderType};
+    use crate::rest::{OrderRequest, Side};
     let api = init_api().await;
-    api.place_order(
-        "BTC-PERP",
-        Side::Buy,
-        None,
-        OrderType::Market,
-        dec!(0.0001),
-        None,
-        None,
-        None,
-        None,
-    )
+    api.place_order(OrderRequest::market("BTC-PERP", Side::Buy, dec!(0.0001)))
         .await
         .expect("Could not place order for testing purposes");
     match ws.next().await.unwrap() {
@@ -365,3 +498,323 @@ async fn orders() {
 
     ws.unsubscribe_all().await.expect("Unsubscribe failed");
 }
+
+#[test]
+fn model_round_trip() {
+    use crate::rest::tests::round_trip;
+
+    round_trip::<Response>(
+        r#"{
+            "channel":"ticker","market":"BTC-PERP","type":"update",
+            "data":{
+                "bid":40000,"ask":40000.5,"bidSize":1.2,"askSize":0.3,"last":40000.5,
+                "time":1621740952.507955
+            }
+        }"#,
+    );
+    round_trip::<Response>(
+        r#"{
+            "channel":"trades","market":"BTC-PERP","type":"update",
+            "data":[{
+                "id":1,"price":40000.5,"size":0.01,"side":"buy","liquidation":false,
+                "time":"2021-05-23T05:24:24.315884+00:00"
+            }]
+        }"#,
+    );
+    let orderbook = round_trip::<OrderbookData>(
+        r#"{
+            "action":"partial","bids":[[40000,1.2],[39999.5,0.5]],"asks":[[40000.5,0.3]],
+            "checksum":1234567890,"time":1621740952.507955
+        }"#,
+    );
+    assert_eq!(OrderbookAction::Partial, orderbook.action);
+    let json = r#"{
+        "id":11,"market":"BTC-PERP","future":"BTC-PERP","baseCurrency":null,
+        "quoteCurrency":null,"type":"order","side":"buy","price":40000.5,"size":0.001,
+        "orderId":103,"tradeId":1034,"time":"2021-05-23T05:24:24.315884+00:00",
+        "fee":0.008,"feeRate":0.0002,"feeCurrency":"USD","liquidity":"taker"
+    }"#;
+    let fill = round_trip::<Fill>(json);
+    assert_eq!(Liquidity::Taker, fill.liquidity);
+    // Websocket fills feed the same pipeline as fills fetched over REST
+    assert_eq!(
+        serde_json::from_str::<FillInfo>(json).unwrap(),
+        FillInfo::from(fill)
+    );
+    let response = round_trip::<Response>(
+        r#"{
+            "channel":"markets","market":null,"type":"partial",
+            "data":{"action":"partial","data":{"BTC-PERP":{
+                "name":"BTC-PERP","enabled":true,"priceIncrement":1.0,"sizeIncrement":0.0001,
+                "type":"future","baseCurrency":null,"quoteCurrency":null,"underlying":"BTC",
+                "restricted":false,"future":{"name":"BTC-PERP","perpetual":true}
+            }}}
+        }"#,
+    );
+    let partial = match response.data {
+        Some(ResponseData::Markets(markets)) => markets,
+        data => panic!("Expected markets, got {:?}.", data),
+    };
+    let mut markets = Markets::new();
+    partial.apply(&mut markets);
+    assert_eq!(MarketType::Future, markets["BTC-PERP"].market_type);
+    let update = round_trip::<MarketsData>(
+        r#"{"action":"update","data":{"ETH/USD":{
+            "name":"ETH/USD","enabled":true,"priceIncrement":0.1,"sizeIncrement":0.001,
+            "type":"spot","baseCurrency":"ETH","quoteCurrency":"USD","underlying":null
+        }}}"#,
+    );
+    update.apply(&mut markets);
+    assert_eq!(2, markets.len());
+    partial.apply(&mut markets);
+    assert_eq!(vec!["BTC-PERP"], markets.keys().collect::<Vec<_>>());
+    // Markets fetched over REST convert to websocket markets and back
+    let json = r#"{
+        "name":"BTC-PERP","type":"future","underlying":"BTC","baseCurrency":null,
+        "quoteCurrency":null,"enabled":true,"ask":40000.5,"bid":40000,"last":40000.5,
+        "postOnly":false,"priceIncrement":0.5,"sizeIncrement":0.0001,"restricted":false,
+        "minProvideSize":0.0001,"price":40000.5,"highLeverageFeeExempt":true,
+        "change1h":0.001,"change24h":-0.02,"changeBod":0.01,
+        "quoteVolume24h":1234567.8,"volumeUsd24h":1234567.8
+    }"#;
+    let market = serde_json::from_str::<rest::Market>(json).unwrap();
+    let converted = Market::from(market.clone());
+    assert_eq!(round_trip::<Market>(json), converted);
+    assert_eq!(market, rest::Market::try_from(converted).unwrap());
+    match rest::Market::try_from(markets["BTC-PERP"].clone()) {
+        Err(Error::IncompleteMarket(name)) if name == "BTC-PERP" => {}
+        result => panic!("Incomplete market error expected, got {:?}.", result),
+    }
+    let response = round_trip::<Response>(r#"{"type":"maintenance","market":null}"#);
+    assert_eq!(Type::Unknown("maintenance".to_string()), response.r#type);
+    round_trip::<Vec<Channel>>(r#"[{"orderbook":"BTC-PERP"},"markets","fills"]"#);
+}
+
+#[tokio::test]
+async fn reconnect() {
+    use tokio_tungstenite::tungstenite::Message;
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let endpoint = format!("ws://{}", listener.local_addr().unwrap());
+    let server = tokio::spawn(async move {
+        for connection in 0..2 {
+            let (socket, _) = listener.accept().await.unwrap();
+            let mut socket = tokio_tungstenite::accept_async(socket).await.unwrap();
+            let mut received = Vec::new();
+            for _ in 0..2 {
+                match socket.next().await {
+                    Some(Ok(Message::Text(text))) => {
+                        received.push(serde_json::from_str::<serde_json::Value>(&text).unwrap())
+                    }
+                    message => panic!("Unexpected message {:?}.", message),
+                }
+            }
+            let (login, subscribe) = (&received[0], &received[1]);
+            assert_eq!(json!("login"), login["op"]);
+            assert_eq!(json!("key"), login["args"]["key"]);
+            assert_eq!(json!("subscribe"), subscribe["op"]);
+            assert_eq!(json!("orderbook"), subscribe["channel"]);
+
+            for message in [
+                json!({"type": "subscribed", "channel": "orderbook", "market": "BTC-PERP"}),
+                json!({
+                    "type": "partial", "channel": "orderbook", "market": "BTC-PERP",
+                    "data": {
+                        "action": "partial", "bids": [[40000.0 + connection as f64, 1.0]],
+                        "asks": [[40010.0, 1.0]], "checksum": 0, "time": 1637000000.0,
+                    },
+                }),
+            ] {
+                socket
+                    .send(Message::Text(message.to_string()))
+                    .await
+                    .unwrap();
+            }
+            if connection == 0 {
+                socket.close(None).await.unwrap();
+            } else {
+                while socket.next().await.is_some() {}
+            }
+        }
+    });
+
+    let mut ws = Ws::connect_with_endpoint(
+        &endpoint,
+        Some(("key".to_string(), "secret".to_string())),
+        None,
+        None,
+        &Clock::new(),
+    )
+    .await
+    .unwrap()
+    .with_reconnect(Backoff {
+        initial: Duration::from_millis(10),
+        max: Duration::from_millis(100),
+    });
+    ws.subscribe(vec![Channel::Orderbook("BTC-PERP".to_string())])
+        .await
+        .unwrap();
+
+    let mut orderbook = Orderbook::new("BTC-PERP".to_string());
+    for bid in [dec!(40000), dec!(40001)] {
+        match ws.next().await {
+            Some(Ok((_, Data::OrderbookData(data)))) => orderbook.update(&data),
+            data => panic!("Expected a partial orderbook, got {:?}.", data),
+        }
+        // The partial after reconnecting replaces the book
+        assert_eq!(
+            vec![(bid, dec!(1))],
+            orderbook.bids.clone().into_iter().collect::<Vec<_>>()
+        );
+        if bid == dec!(40000) {
+            assert!(matches!(
+                ws.next().await,
+                Some(Ok((None, Data::Reconnected)))
+            ));
+        }
+    }
+    drop(ws);
+    server.await.unwrap();
+}
+
+/// Reads the `op` and `channel` of the next `count` messages to a test
+/// server, skipping pings.
+async fn receive(
+    socket: &mut tokio_tungstenite::WebSocketStream<tokio::net::TcpStream>,
+    count: usize,
+) -> Vec<(serde_json::Value, serde_json::Value)> {
+    use tokio_tungstenite::tungstenite::Message;
+
+    let mut received = Vec::new();
+    while received.len() < count {
+        match socket.next().await {
+            Some(Ok(Message::Text(text))) => {
+                let message: serde_json::Value = serde_json::from_str(&text).unwrap();
+                if message["op"] != json!("ping") {
+                    received.push((message["op"].clone(), message["channel"].clone()));
+                }
+            }
+            message => panic!("Unexpected message {:?}.", message),
+        }
+    }
+    received
+}
+
+#[tokio::test]
+async fn reconnect_while_subscribing() {
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use tokio_tungstenite::tungstenite::Message;
+
+    let op = |op: &str, channel: &str| (json!(op), json!(channel));
+    let ack = |r#type: &str, channel: &str| {
+        Message::Text(json!({"type": r#type, "channel": channel, "market": "BTC-PERP"}).to_string())
+    };
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let endpoint = format!("ws://{}", listener.local_addr().unwrap());
+    let ticker_confirmed = Arc::new(AtomicBool::new(false));
+    let confirmed = ticker_confirmed.clone();
+    let server = tokio::spawn(async move {
+        let accept = || async {
+            let (socket, _) = listener.accept().await.unwrap();
+            let mut socket = tokio_tungstenite::accept_async(socket).await.unwrap();
+            assert_eq!(json!("login"), receive(&mut socket, 1).await[0].0);
+            socket
+        };
+
+        let mut socket = accept().await;
+        assert_eq!(
+            vec![op("subscribe", "orderbook")],
+            receive(&mut socket, 1).await
+        );
+        socket.send(ack("subscribed", "orderbook")).await.unwrap();
+        assert_eq!(
+            vec![op("subscribe", "trades")],
+            receive(&mut socket, 1).await
+        );
+        socket.send(ack("subscribed", "trades")).await.unwrap();
+        // Dropped before confirming the unsubscription
+        assert_eq!(
+            vec![op("unsubscribe", "orderbook")],
+            receive(&mut socket, 1).await
+        );
+        socket.close(None).await.unwrap();
+        drop(socket);
+
+        // The channel that was being unsubscribed from is not subscribed to
+        // again
+        let mut socket = accept().await;
+        assert_eq!(
+            vec![op("subscribe", "trades")],
+            receive(&mut socket, 1).await
+        );
+        socket.send(ack("subscribed", "trades")).await.unwrap();
+        // Dropped before confirming the subscription
+        assert_eq!(
+            vec![op("subscribe", "ticker")],
+            receive(&mut socket, 1).await
+        );
+        socket.close(None).await.unwrap();
+        drop(socket);
+
+        // The pending subscription is sent again, and confirming another
+        // channel does not confirm it
+        let mut socket = accept().await;
+        assert_eq!(
+            vec![op("subscribe", "trades"), op("subscribe", "ticker")],
+            receive(&mut socket, 2).await
+        );
+        socket.send(ack("subscribed", "trades")).await.unwrap();
+        time::sleep(Duration::from_millis(100)).await;
+        confirmed.store(true, Ordering::SeqCst);
+        socket.send(ack("subscribed", "ticker")).await.unwrap();
+        while socket.next().await.is_some() {}
+    });
+
+    let client = async {
+        let mut ws = Ws::connect_with_endpoint(
+            &endpoint,
+            Some(("key".to_string(), "secret".to_string())),
+            None,
+            None,
+            &Clock::new(),
+        )
+        .await
+        .unwrap()
+        .with_reconnect(Backoff {
+            initial: Duration::from_millis(10),
+            max: Duration::from_millis(100),
+        });
+        ws.subscribe(vec![
+            Channel::Orderbook("BTC-PERP".to_string()),
+            Channel::Trades("BTC-PERP".to_string()),
+        ])
+        .await
+        .unwrap();
+
+        ws.unsubscribe(vec![Channel::Orderbook("BTC-PERP".to_string())])
+            .await
+            .unwrap();
+        ws.subscribe(vec![Channel::Ticker("BTC-PERP".to_string())])
+            .await
+            .unwrap();
+        assert!(ticker_confirmed.load(Ordering::SeqCst));
+        assert_eq!(
+            vec![
+                Channel::Trades("BTC-PERP".to_string()),
+                Channel::Ticker("BTC-PERP".to_string()),
+            ],
+            ws.channels
+        );
+        for _ in 0..2 {
+            assert!(matches!(
+                ws.next().await,
+                Some(Ok((None, Data::Reconnected)))
+            ));
+        }
+    };
+    time::timeout(Duration::from_secs(10), client)
+        .await
+        .expect("Subscribing did not complete.");
+    server.await.unwrap();
+}